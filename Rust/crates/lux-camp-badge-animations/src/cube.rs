@@ -0,0 +1,326 @@
+use crate::noise::perlin;
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::time::Duration;
+use lux_camp_badge::led::{hsv_rgb_convert::*, Animation, Dimmable, LedMatrix};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+use smart_leds_trait::{SmartLedsWrite, RGB8};
+
+/// Layers of the cube, `z` being the vertical axis with the bottom layer at `0`.
+/// 2D matrices are treated as a cube with a single layer.
+fn depth<C: LedMatrix>() -> usize {
+    <C as LedMatrix>::Z.max(1)
+}
+
+/// Drops falling from the top layer to the bottom, leaving a fading trail.
+pub struct Rain {
+    rng: SmallRng,
+    color: RGB8,
+    density: f64,
+    /// The `x` / `y` / `z` coordinate of every drop.
+    drops: Vec<(usize, usize, usize)>,
+}
+
+impl Rain {
+    /// `density` is the probability of a new drop above each pixel per frame.
+    /// It's limited to `0.0` to `1.0`, NaN or infinite densities result in no drops at all.
+    pub fn build<Matrix, Driver>(
+        seed: u64,
+        color: RGB8,
+        density: f64,
+    ) -> Box<dyn Animation<Matrix> + Send>
+    where
+        Matrix: LedMatrix<Driver = Driver>,
+        Driver: SmartLedsWrite<Color = RGB8>,
+    {
+        Box::new(Self {
+            rng: SmallRng::seed_from_u64(seed),
+            color,
+            density: if density.is_finite() {
+                density.clamp(0.0, 1.0)
+            } else {
+                0.0
+            },
+            drops: Vec::new(),
+        })
+    }
+}
+
+impl<B, C: LedMatrix<Driver = B>> Animation<C> for Rain
+where
+    B: SmartLedsWrite<Color = RGB8>,
+{
+    fn init(&mut self, _matrix: &mut C) -> Option<Duration> {
+        self.drops.clear();
+        Some(Duration::from_millis(80))
+    }
+
+    fn update(&mut self, _tick: Duration, matrix: &mut C) {
+        let mut frame = matrix.frame();
+        frame.as_mut().iter_mut().for_each(|pixel| pixel.dimm(96));
+        matrix.set_buf(frame.as_mut());
+
+        // Drops reaching the bottom disappear
+        self.drops.retain_mut(|(_, _, z)| match z.checked_sub(1) {
+            Some(below) => {
+                *z = below;
+                true
+            }
+            None => false,
+        });
+        for y in 0..<C as LedMatrix>::Y {
+            for x in 0..<C as LedMatrix>::X {
+                if self.rng.gen_bool(self.density) {
+                    self.drops.push((x, y, depth::<C>() - 1));
+                }
+            }
+        }
+        for (x, y, z) in &self.drops {
+            matrix.set_3d(*x, *y, *z, &self.color);
+        }
+    }
+
+    fn name(&self) -> &str {
+        "rain"
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![("density", self.density.to_string())]
+    }
+}
+
+/// The axis along which a [PlaneSweep] moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Axis {
+    X,
+    Y,
+    Z,
+}
+
+/// A plane moving back and forth through the cube, changing its color at every turn.
+pub struct PlaneSweep {
+    axis: Axis,
+    step: Duration,
+    position: usize,
+    forward: bool,
+    hue: u8,
+}
+
+impl PlaneSweep {
+    /// The plane is perpendicular to `axis` and moves by one pixel every `step`.
+    pub fn build<Matrix, Driver>(axis: Axis, step: Duration) -> Box<dyn Animation<Matrix> + Send>
+    where
+        Matrix: LedMatrix<Driver = Driver>,
+        Driver: SmartLedsWrite<Color = RGB8>,
+    {
+        Box::new(Self {
+            axis,
+            step,
+            position: 0,
+            forward: true,
+            hue: 0,
+        })
+    }
+}
+
+impl<B, C: LedMatrix<Driver = B>> Animation<C> for PlaneSweep
+where
+    B: SmartLedsWrite<Color = RGB8>,
+{
+    fn init(&mut self, _matrix: &mut C) -> Option<Duration> {
+        self.position = 0;
+        self.forward = true;
+        Some(self.step)
+    }
+
+    fn update(&mut self, _tick: Duration, matrix: &mut C) {
+        let (width, height, depth) = (<C as LedMatrix>::X, <C as LedMatrix>::Y, depth::<C>());
+        let color = <Hsv8 as Hsv2Rgb>::hsv2rgb(Hsv8 {
+            hue: self.hue,
+            sat: 255,
+            val: 255,
+        });
+        let mut frame = matrix.frame();
+        frame.as_mut().fill(RGB8::default());
+        matrix.set_buf(frame.as_mut());
+        for z in 0..depth {
+            for y in 0..height {
+                for x in 0..width {
+                    let on_plane = match self.axis {
+                        Axis::X => x,
+                        Axis::Y => y,
+                        Axis::Z => z,
+                    } == self.position;
+                    if on_plane {
+                        matrix.set_3d(x, y, z, &color);
+                    }
+                }
+            }
+        }
+
+        let length = match self.axis {
+            Axis::X => width,
+            Axis::Y => height,
+            Axis::Z => depth,
+        };
+        if length < 2 {
+            return;
+        }
+        // Bounce at both ends
+        match (self.forward, self.position) {
+            (true, p) if p + 1 >= length => self.forward = false,
+            (false, 0) => self.forward = true,
+            _ => {}
+        }
+        if self.forward {
+            self.position += 1;
+        } else {
+            self.position -= 1;
+        }
+        if self.position == 0 || self.position + 1 == length {
+            self.hue = self.hue.wrapping_add(48);
+        }
+    }
+
+    fn name(&self) -> &str {
+        "plane-sweep"
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("axis", format!("{:?}", self.axis)),
+            ("step", format!("{:?}", self.step)),
+        ]
+    }
+}
+
+/// Colors flowing through the cube, based on 3D perlin noise.
+pub struct Noise {
+    scale_factor: f32,
+    frame: f32,
+}
+
+impl Noise {
+    /// The smaller `scale_factor`, the smoother the colors change from pixel to pixel.
+    pub fn build<Matrix, Driver>(scale_factor: f32) -> Box<dyn Animation<Matrix> + Send>
+    where
+        Matrix: LedMatrix<Driver = Driver>,
+        Driver: SmartLedsWrite<Color = RGB8>,
+    {
+        Box::new(Self {
+            scale_factor,
+            frame: 0.,
+        })
+    }
+}
+
+impl<B, C: LedMatrix<Driver = B>> Animation<C> for Noise
+where
+    B: SmartLedsWrite<Color = RGB8>,
+{
+    fn init(&mut self, _matrix: &mut C) -> Option<Duration> {
+        Some(Duration::from_millis(100))
+    }
+
+    fn update(&mut self, _tick: Duration, matrix: &mut C) {
+        let (scale, frame) = (self.scale_factor, self.frame);
+        for z in 0..depth::<C>() {
+            for y in 0..<C as LedMatrix>::Y {
+                for x in 0..<C as LedMatrix>::X {
+                    // The noise drifts sideways and rises over time
+                    let hue = perlin(
+                        x as f32 * scale + frame,
+                        y as f32 * scale,
+                        z as f32 * scale - frame * 0.5,
+                    );
+                    let color = <Hsv8 as Hsv2Rgb>::hsv2rgb(Hsv8 {
+                        hue: ((hue * 255.0) + 127.0) as u8,
+                        sat: 255,
+                        val: 255,
+                    });
+                    matrix.set_3d(x, y, z, &color);
+                }
+            }
+        }
+        self.frame = frame + 0.05;
+    }
+
+    fn name(&self) -> &str {
+        "noise-3d"
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![("scale_factor", self.scale_factor.to_string())]
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TestDriver;
+    use lux_camp_badge::led::framebuffer::CubeBuffer;
+
+    type Cube = CubeBuffer<TestDriver, 2, 2, 3>;
+
+    /// Whether every pixel of layer `z` is `color`.
+    fn layer(cube: &Cube, z: usize, color: RGB8) -> bool {
+        (0..2).all(|y| (0..2).all(|x| cube.get_3d(x, y, z) == Some(&color)))
+    }
+
+    #[test]
+    fn rain() {
+        let blue = RGB8::new(0, 0, 255);
+        let off = RGB8::default();
+        let mut cube = Cube::new();
+        let mut rain = Rain::build(0, blue, 1.0);
+        rain.init(&mut cube);
+
+        rain.update(Duration::ZERO, &mut cube);
+        assert!(layer(&cube, 2, blue));
+        assert!(layer(&cube, 1, off));
+
+        // The first drops fall through the cube, new ones keep coming
+        rain.update(Duration::ZERO, &mut cube);
+        rain.update(Duration::ZERO, &mut cube);
+        assert!((0..3).all(|z| layer(&cube, z, blue)));
+        rain.update(Duration::ZERO, &mut cube);
+        assert_eq!(rain.params(), [("density", "1".to_string())]);
+
+        let mut dry = Rain::build(0, blue, f64::NAN);
+        dry.init(&mut cube);
+        dry.update(Duration::ZERO, &mut cube);
+        assert_eq!(dry.params(), [("density", "0".to_string())]);
+    }
+
+    #[test]
+    fn plane_sweep() {
+        let mut cube = Cube::new();
+        let mut sweep = PlaneSweep::build(Axis::Z, Duration::from_millis(100));
+        assert_eq!(sweep.init(&mut cube), Some(Duration::from_millis(100)));
+
+        let mut positions = Vec::new();
+        for _ in 0..6 {
+            sweep.update(Duration::ZERO, &mut cube);
+            let lit = (0..3).filter(|z| !layer(&cube, *z, RGB8::default()));
+            positions.push(lit.collect::<Vec<_>>());
+        }
+        assert_eq!(positions, [[0], [1], [2], [1], [0], [1]]);
+    }
+
+    #[test]
+    fn noise() {
+        let mut cube = Cube::new();
+        let mut noise = Noise::build(0.3);
+        noise.init(&mut cube);
+        noise.update(Duration::ZERO, &mut cube);
+        let first = cube.read_buf().to_vec();
+        assert!(first.iter().all(|pixel| *pixel != RGB8::default()));
+        noise.update(Duration::ZERO, &mut cube);
+        assert_ne!(cube.read_buf(), first);
+    }
+}
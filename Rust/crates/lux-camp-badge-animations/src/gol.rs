@@ -1,7 +1,14 @@
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::time::Duration;
 use lux_camp_badge::led::{Animation, LedMatrix};
 use rand::{rngs::SmallRng, Rng, SeedableRng};
 use smart_leds_trait::{SmartLedsWrite, RGB, RGB8};
-use std::time::Duration;
 
 pub struct Gol<Color, const X: usize, const Y: usize> {
     rng: SmallRng,
@@ -99,6 +106,19 @@ where
         self.frame_rate
     }
 
+    fn name(&self) -> &str {
+        "gol"
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("start_probability", self.start_probability.to_string()),
+            ("max_generations", format!("{:?}", self.max_generations)),
+            ("frame_rate", format!("{:?}", self.frame_rate)),
+            ("generations", self.generations.to_string()),
+        ]
+    }
+
     fn update(&mut self, _tick: Duration, matrix: &mut C) {
         if !self.alive || self.max_generations.is_some_and(|n| self.generations >= n) {
             self.init(matrix);
@@ -0,0 +1,295 @@
+use alloc::{boxed::Box, format, string::String, sync::Arc, vec::Vec};
+use core::{
+    sync::atomic::{AtomicU8, Ordering},
+    time::Duration,
+};
+use lux_camp_badge::led::{Animation, Blend, LedMatrix};
+use smart_leds_trait::{SmartLedsWrite, RGB8};
+
+/// How the pixels of a layer are combined with the layers below.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BlendMode {
+    /// The layer covers the layers below. Pixels which are off are transparent.
+    Normal,
+    /// Add up the channels, e.g. to overlay light effects.
+    Add,
+    /// Multiply the channels, e.g. to mask the layers below.
+    Multiply,
+    /// Inverse of multiplying the inverted channels, brightens like two projectors.
+    Screen,
+    /// The brighter of both channels.
+    Max,
+}
+
+impl BlendMode {
+    /// Combine the pixel `top` of a layer with the pixel `bottom` below it.
+    pub fn apply(&self, bottom: RGB8, top: RGB8) -> RGB8 {
+        let channel = |a: u8, b: u8| match self {
+            Self::Normal => b,
+            Self::Add => a.saturating_add(b),
+            Self::Multiply => (a as u16 * b as u16 / 255) as u8,
+            Self::Screen => 255 - ((255 - a) as u16 * (255 - b) as u16 / 255) as u8,
+            Self::Max => a.max(b),
+        };
+        if *self == Self::Normal && top == RGB8::default() {
+            return bottom;
+        }
+        RGB8::new(
+            channel(bottom.r, top.r),
+            channel(bottom.g, top.g),
+            channel(bottom.b, top.b),
+        )
+    }
+}
+
+/// Opacity of a layer, from `0` (invisible) to `255` (opaque).
+///
+/// Clones share the same value, so keep a clone to change the opacity
+/// while the [Layers] are running on the matrix.
+#[derive(Clone, Debug, Default)]
+pub struct Opacity(Arc<AtomicU8>);
+
+impl Opacity {
+    pub fn new(opacity: u8) -> Self {
+        Self(Arc::new(AtomicU8::new(opacity)))
+    }
+
+    pub fn get(&self) -> u8 {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    pub fn set(&self, opacity: u8) {
+        self.0.store(opacity, Ordering::Relaxed)
+    }
+}
+
+struct Layer<C: LedMatrix> {
+    animation: Box<dyn Animation<C> + Send>,
+    mode: BlendMode,
+    opacity: Opacity,
+    /// Off-screen frame buffer of the animation, created on init.
+    buffer: Option<C>,
+    frame_rate: Option<Duration>,
+    tick: Option<Duration>,
+}
+
+/// Stack several animations on top of each other.
+///
+/// Each layer renders into its own copy of the matrix, which is then blended into the
+/// layers below according to its [BlendMode] and [Opacity]. The first layer is at the bottom.
+/// Every layer is updated at the frame rate it returned from [Animation::init].
+///
+/// ```ignore
+/// let overlay = Opacity::new(128);
+/// let layers = Layers::new()
+///     .layer(noise::PerlinAnimation::build(), BlendMode::Normal, Opacity::new(255))
+///     .layer(random::Flip::build(seed), BlendMode::Screen, overlay.clone())
+///     .build();
+/// // Later on, e.g. in a request handler
+/// overlay.set(255);
+/// ```
+pub struct Layers<C: LedMatrix>(Vec<Layer<C>>);
+
+impl<B, C: LedMatrix<Driver = B> + Clone + 'static> Layers<C>
+where
+    B: SmartLedsWrite<Color = RGB8>,
+{
+    pub fn new() -> Self {
+        Self(Vec::new())
+    }
+
+    /// Put `animation` on top of the layers added so far.
+    pub fn layer(
+        mut self,
+        animation: Box<dyn Animation<C> + Send>,
+        mode: BlendMode,
+        opacity: Opacity,
+    ) -> Self {
+        self.0.push(Layer {
+            animation,
+            mode,
+            opacity,
+            buffer: None,
+            frame_rate: None,
+            tick: None,
+        });
+        self
+    }
+
+    pub fn build(self) -> Box<dyn Animation<C> + Send>
+    where
+        C: Send,
+    {
+        Box::new(self)
+    }
+
+    /// Blend all layers into `matrix`.
+    fn composite(&self, matrix: &mut C) {
+        let mut frame = matrix.frame();
+        let buf = frame.as_mut();
+        buf.fill(RGB8::default());
+        for layer in &self.0 {
+            let (Some(buffer), opacity) = (&layer.buffer, layer.opacity.get()) else {
+                continue;
+            };
+            if opacity == 0 {
+                continue;
+            }
+            for (bottom, top) in buf.iter_mut().zip(buffer.read_buf()) {
+                *bottom = bottom.blend(&layer.mode.apply(*bottom, *top), opacity);
+            }
+        }
+        matrix.set_buf(buf);
+    }
+}
+
+impl<B, C: LedMatrix<Driver = B> + Clone + 'static> Default for Layers<C>
+where
+    B: SmartLedsWrite<Color = RGB8>,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B, C: LedMatrix<Driver = B> + Clone + 'static> Animation<C> for Layers<C>
+where
+    B: SmartLedsWrite<Color = RGB8>,
+{
+    fn init(&mut self, matrix: &mut C) -> Option<Duration> {
+        for layer in &mut self.0 {
+            let mut buffer = matrix.clone();
+            let mut blank = matrix.frame();
+            blank.as_mut().fill(RGB8::default());
+            buffer.set_buf(blank.as_mut());
+            layer.frame_rate = layer.animation.init(&mut buffer);
+            layer.buffer = Some(buffer);
+            layer.tick = None;
+        }
+        self.composite(matrix);
+        None
+    }
+
+    fn name(&self) -> &str {
+        "layers"
+    }
+
+    /// Every layer from the bottom as `name (mode, opacity)`.
+    fn params(&self) -> Vec<(&'static str, String)> {
+        self.0
+            .iter()
+            .map(|layer| {
+                let name = layer.animation.name();
+                let (mode, opacity) = (layer.mode, layer.opacity.get());
+                ("layer", format!("{name} ({mode:?}, {opacity})"))
+            })
+            .collect()
+    }
+
+    fn update(&mut self, tick: Duration, matrix: &mut C) {
+        for layer in &mut self.0 {
+            let Some(buffer) = &mut layer.buffer else {
+                continue;
+            };
+            let due = match (layer.tick, layer.frame_rate) {
+                (Some(last), Some(step)) => last.saturating_add(step) <= tick,
+                _ => true,
+            };
+            if due {
+                layer.tick = Some(tick);
+                layer.animation.update(tick, buffer);
+            }
+        }
+        self.composite(matrix);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TestDriver;
+
+    /// A 2x1 matrix.
+    #[derive(Clone, Default)]
+    struct Test([RGB8; 2]);
+
+    impl LedMatrix for Test {
+        type Driver = TestDriver;
+        type Frame = [RGB8; 2];
+        const X: usize = 2;
+        const Y: usize = 1;
+
+        fn frame(&self) -> Self::Frame {
+            self.0
+        }
+
+        fn read_buf(&self) -> &[RGB8] {
+            &self.0
+        }
+
+        fn set_buf(&mut self, buf: &mut [RGB8]) {
+            self.0.copy_from_slice(buf);
+        }
+
+        fn set_2d(&mut self, x: usize, y: usize, color: &RGB8) {
+            self.0[Self::index_2d(x, y)] = *color;
+        }
+
+        fn index_2d(x: usize, _y: usize) -> usize {
+            x
+        }
+    }
+
+    /// Draws the given pixels on every update.
+    struct Pixels([RGB8; 2]);
+
+    impl Animation<Test> for Pixels {
+        fn update(&mut self, _tick: Duration, matrix: &mut Test) {
+            matrix.set_buf(&mut self.0.clone());
+        }
+    }
+
+    #[test]
+    fn modes() {
+        let a = RGB8::new(200, 100, 0);
+        let b = RGB8::new(100, 255, 0);
+        assert_eq!(BlendMode::Normal.apply(a, b), b);
+        assert_eq!(BlendMode::Normal.apply(a, RGB8::default()), a);
+        assert_eq!(BlendMode::Add.apply(a, b), RGB8::new(255, 255, 0));
+        assert_eq!(BlendMode::Multiply.apply(a, b), RGB8::new(78, 100, 0));
+        assert_eq!(BlendMode::Screen.apply(a, b), RGB8::new(222, 255, 0));
+        assert_eq!(BlendMode::Max.apply(a, b), RGB8::new(200, 255, 0));
+    }
+
+    #[test]
+    fn composite() {
+        let red = RGB8::new(255, 0, 0);
+        let blue = RGB8::new(0, 0, 255);
+        let off = RGB8::default();
+        let opacity = Opacity::new(255);
+        let mut layers = Layers::new()
+            .layer(
+                Box::new(Pixels([red, red])),
+                BlendMode::Normal,
+                Opacity::new(255),
+            )
+            .layer(
+                Box::new(Pixels([blue, off])),
+                BlendMode::Normal,
+                opacity.clone(),
+            );
+
+        let mut matrix = Test::default();
+        assert_eq!(layers.init(&mut matrix), None);
+        layers.update(Duration::ZERO, &mut matrix);
+        assert_eq!(matrix.0, [blue, red]);
+
+        opacity.set(0);
+        layers.update(Duration::ZERO, &mut matrix);
+        assert_eq!(matrix.0, [red, red]);
+
+        opacity.set(128);
+        layers.update(Duration::ZERO, &mut matrix);
+        assert_eq!(matrix.0, [RGB8::new(127, 0, 128), red]);
+    }
+}
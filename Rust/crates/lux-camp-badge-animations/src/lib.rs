@@ -1,22 +1,56 @@
+#![cfg_attr(not(feature = "std"), no_std)]
 // This is common when iterating over 2D matrices. In our context,
 // using iterators instead, makes the code less readable for no benefits.
 #![allow(clippy::needless_range_loop)]
+
+extern crate alloc;
+
+/// Animations for 3D LED cubes
+pub mod cube;
 /// Game of life, you know the rules.
 pub mod gol;
+/// Stack animations on top of each other with blend modes
+pub mod layers;
 /// Animations based on noise functions
 pub mod noise;
+/// Rotate through a list of animations on a schedule
+pub mod playlist;
 /// Rainbow animations (classic HSV)
 pub mod rainbow;
 /// Animations that draw pixels randomly
 pub mod random;
 /// Static images or a set of static images played at constant frame rate
 pub mod scene;
+/// Scrolling text in a built-in 5 pixel font
+pub mod text;
 
 /// All available animation modules.
 pub mod prelude {
+    pub use crate::cube;
     pub use crate::gol;
+    pub use crate::layers;
     pub use crate::noise;
+    pub use crate::playlist;
     pub use crate::rainbow;
     pub use crate::random;
     pub use crate::scene;
+    pub use crate::text;
+}
+
+/// Driver discarding all pixels, for the tests of the animations.
+#[cfg(test)]
+pub(crate) struct TestDriver;
+
+#[cfg(test)]
+impl smart_leds_trait::SmartLedsWrite for TestDriver {
+    type Error = ();
+    type Color = smart_leds_trait::RGB8;
+
+    fn write<T, I>(&mut self, _iterator: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = I>,
+        I: Into<Self::Color>,
+    {
+        Ok(())
+    }
 }
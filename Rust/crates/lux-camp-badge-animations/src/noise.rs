@@ -1,9 +1,84 @@
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
 use lux_camp_badge::led::{Animation, LedMatrix};
 
-use glm;
+use core::time::Duration;
 use smart_leds::hsv::{hsv2rgb, Hsv};
 use smart_leds_trait::{SmartLedsWrite, RGB8};
-use std::time::Duration;
+
+/// 3D perlin noise in about `-1.0..=1.0`, changing smoothly in every direction.
+///
+/// Returns `0.0` at whole numbers.
+pub(crate) fn perlin(x: f32, y: f32, z: f32) -> f32 {
+    let (xi, yi, zi) = (floor(x), floor(y), floor(z));
+    let (xf, yf, zf) = (x - xi as f32, y - yi as f32, z - zi as f32);
+    let corner = |dx: i32, dy: i32, dz: i32| {
+        let hash = hash(xi + dx, yi + dy, zi + dz);
+        gradient(hash, xf - dx as f32, yf - dy as f32, zf - dz as f32)
+    };
+    let (u, v, w) = (fade(xf), fade(yf), fade(zf));
+    let bottom = lerp(
+        v,
+        lerp(u, corner(0, 0, 0), corner(1, 0, 0)),
+        lerp(u, corner(0, 1, 0), corner(1, 1, 0)),
+    );
+    let top = lerp(
+        v,
+        lerp(u, corner(0, 0, 1), corner(1, 0, 1)),
+        lerp(u, corner(0, 1, 1), corner(1, 1, 1)),
+    );
+    lerp(w, bottom, top)
+}
+
+/// `f32::floor` is not available without std.
+fn floor(x: f32) -> i32 {
+    let truncated = x as i32;
+    if truncated as f32 > x {
+        truncated - 1
+    } else {
+        truncated
+    }
+}
+
+fn fade(t: f32) -> f32 {
+    t * t * t * (t * (t * 6.0 - 15.0) + 10.0)
+}
+
+fn lerp(t: f32, a: f32, b: f32) -> f32 {
+    a + t * (b - a)
+}
+
+/// Pseudo random bits for a point of the grid, instead of perlin's permutation table.
+fn hash(x: i32, y: i32, z: i32) -> u32 {
+    let mut hash = (x as u32).wrapping_mul(0x8da6_b343)
+        ^ (y as u32).wrapping_mul(0xd816_3841)
+        ^ (z as u32).wrapping_mul(0xcb1a_b31f);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0x5bd1_e995);
+    hash ^ (hash >> 15)
+}
+
+/// Dot product of the offset to a grid point with one of the 12 gradients of perlin noise.
+fn gradient(hash: u32, x: f32, y: f32, z: f32) -> f32 {
+    match hash % 12 {
+        0 => x + y,
+        1 => y - x,
+        2 => x - y,
+        3 => -x - y,
+        4 => x + z,
+        5 => z - x,
+        6 => x - z,
+        7 => -x - z,
+        8 => y + z,
+        9 => z - y,
+        10 => y - z,
+        _ => -y - z,
+    }
+}
 
 struct PerlinNoiseLight {
     frame: f32,
@@ -39,26 +114,58 @@ where
         Some(Duration::from_millis(100))
     }
 
+    fn name(&self) -> &str {
+        "perlin"
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![("scale_factor", self.0.scale_factor.to_string())]
+    }
+
     fn update(&mut self, _tick: Duration, matrix: &mut C) {
-        let mut buf = Vec::with_capacity(<C as LedMatrix>::AREA);
+        let mut buf = matrix.frame();
+        let pixels = buf.as_mut();
         let frame = self.0.frame;
         for x in 0..<C as LedMatrix>::X {
             for y in 0..<C as LedMatrix>::Y {
-                let hue = glm::builtin::noise1(glm::vec3(
+                let hue = perlin(
                     x as f32 * self.0.scale_factor,
                     y as f32 * self.0.scale_factor,
                     frame,
-                ));
+                );
                 let hue = ((hue * 255.0) + 127.0) as u8;
-                buf.push(hsv2rgb(Hsv {
+                pixels[x * <C as LedMatrix>::Y + y] = hsv2rgb(Hsv {
                     hue,
                     sat: 255,
                     val: 255,
-                }));
+                });
             }
         }
         self.0.frame = frame + 0.05;
 
-        matrix.set_buf(&mut buf);
+        matrix.set_buf(pixels);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn perlin_noise() {
+        assert_eq!(perlin(1.0, -2.0, 3.0), 0.0);
+        assert_eq!(perlin(0.3, 1.7, -0.2), perlin(0.3, 1.7, -0.2));
+
+        let samples: Vec<f32> = (0..1000)
+            .map(|i| perlin(i as f32 * 0.37, i as f32 * -0.13, i as f32 * 0.071))
+            .collect();
+        assert!(samples.iter().all(|noise| (-1.0..=1.0).contains(noise)));
+        assert!(samples.iter().any(|noise| *noise > 0.2));
+        assert!(samples.iter().any(|noise| *noise < -0.2));
+
+        // Close points have similar values, also across the grid
+        for x in [-1.02, -0.5, 0.98, 2.5] {
+            assert!((perlin(x, 0.4, 0.6) - perlin(x + 0.04, 0.4, 0.6)).abs() < 0.1);
+        }
     }
 }
@@ -0,0 +1,256 @@
+use alloc::{
+    boxed::Box,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::time::Duration;
+use lux_camp_badge::led::{Animation, LedMatrix};
+use rand::{rngs::SmallRng, Rng, SeedableRng};
+
+/// Play a list of animations one after another, each for its own duration.
+///
+/// Every animation is initialized when its turn comes and updated at the frame rate it
+/// returned from [Animation::init]. The playlist itself is updated on every frame of the
+/// matrix, so the matrix FPS limits the frame rate of the children.
+///
+/// ```ignore
+/// let playlist = Playlist::new()
+///     .add(rainbow::Fade::build(1, None), Duration::from_secs(30))
+///     .add(random::Flip::build(seed), Duration::from_secs(60))
+///     .shuffle(seed)
+///     .build();
+/// ```
+pub struct Playlist<C: LedMatrix> {
+    entries: Vec<(Box<dyn Animation<C> + Send>, Duration)>,
+    order: Order,
+    current: usize,
+    /// When the current entry started, set by its first update.
+    started: Option<Duration>,
+    frame_rate: Option<Duration>,
+    /// The last update of the current entry.
+    tick: Option<Duration>,
+}
+
+/// In which order the entries of a [Playlist] are played.
+enum Order {
+    Sequential,
+    Shuffle(SmallRng),
+}
+
+impl<C: LedMatrix + 'static> Playlist<C> {
+    /// An empty playlist, playing its entries in the order they were added.
+    pub fn new() -> Self {
+        Self {
+            entries: Vec::new(),
+            order: Order::Sequential,
+            current: 0,
+            started: None,
+            frame_rate: None,
+            tick: None,
+        }
+    }
+
+    /// Append `animation` to the playlist, playing it for `duration` whenever it's its turn.
+    pub fn add(mut self, animation: Box<dyn Animation<C> + Send>, duration: Duration) -> Self {
+        self.entries.push((animation, duration));
+        self
+    }
+
+    /// Play the entries in random order instead. The same entry never plays twice in a row.
+    pub fn shuffle(mut self, seed: u64) -> Self {
+        self.order = Order::Shuffle(SmallRng::seed_from_u64(seed));
+        self
+    }
+
+    pub fn build(self) -> Box<dyn Animation<C> + Send> {
+        Box::new(self)
+    }
+
+    /// Index of the entry to play after the current one.
+    fn next(&mut self) -> usize {
+        let len = self.entries.len();
+        match &mut self.order {
+            Order::Sequential => (self.current + 1) % len,
+            Order::Shuffle(_) if len < 2 => 0,
+            Order::Shuffle(rng) => (self.current + rng.gen_range(1..len)) % len,
+        }
+    }
+
+    /// Switch to the entry at `index` and initialize it.
+    fn enter(&mut self, index: usize, matrix: &mut C) {
+        self.current = index;
+        self.started = None;
+        self.tick = None;
+        self.frame_rate = self.entries[index].0.init(matrix);
+    }
+}
+
+impl<C: LedMatrix + 'static> Default for Playlist<C> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<C: LedMatrix + 'static> Animation<C> for Playlist<C> {
+    fn init(&mut self, matrix: &mut C) -> Option<Duration> {
+        if self.entries.is_empty() {
+            return Some(Duration::MAX);
+        }
+        let first = match &mut self.order {
+            Order::Sequential => 0,
+            Order::Shuffle(rng) => rng.gen_range(0..self.entries.len()),
+        };
+        self.enter(first, matrix);
+        None
+    }
+
+    fn name(&self) -> &str {
+        "playlist"
+    }
+
+    /// The current entry and its parameters.
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let Some((current, _)) = self.entries.get(self.current) else {
+            return Vec::new();
+        };
+        let mut params = vec![("current", current.name().to_string())];
+        params.extend(current.params());
+        params
+    }
+
+    fn update(&mut self, tick: Duration, matrix: &mut C) {
+        if self.entries.is_empty() {
+            return;
+        }
+        let started = *self.started.get_or_insert(tick);
+        if tick.saturating_sub(started) >= self.entries[self.current].1 {
+            let next = self.next();
+            self.enter(next, matrix);
+            self.started = Some(tick);
+        }
+
+        let due = match (self.tick, self.frame_rate) {
+            (Some(last), Some(step)) => last.saturating_add(step) <= tick,
+            _ => true,
+        };
+        if due {
+            self.tick = Some(tick);
+            self.entries[self.current].0.update(tick, matrix);
+        }
+    }
+}
+
+// The probes log to a `Mutex`, which requires `std`
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use crate::TestDriver;
+    use smart_leds_trait::RGB8;
+    use std::sync::{Arc, Mutex};
+
+    struct Test;
+
+    impl LedMatrix for Test {
+        type Driver = TestDriver;
+        type Frame = [RGB8; 0];
+        const X: usize = 1;
+        const Y: usize = 1;
+
+        fn frame(&self) -> Self::Frame {
+            []
+        }
+
+        fn read_buf(&self) -> &[RGB8] {
+            &[]
+        }
+
+        fn set_buf(&mut self, _buf: &mut [RGB8]) {}
+
+        fn set_2d(&mut self, _x: usize, _y: usize, _color: &RGB8) {}
+
+        fn index_2d(_x: usize, _y: usize) -> usize {
+            0
+        }
+    }
+
+    /// Records `(id, "init")` and `(id, tick)` of each call.
+    type Log = Arc<Mutex<Vec<(usize, Option<u64>)>>>;
+
+    struct Probe(usize, Option<Duration>, Log);
+
+    impl Animation<Test> for Probe {
+        fn init(&mut self, _matrix: &mut Test) -> Option<Duration> {
+            self.2.lock().unwrap().push((self.0, None));
+            self.1
+        }
+
+        fn update(&mut self, tick: Duration, _matrix: &mut Test) {
+            let tick = tick.as_millis() as u64;
+            self.2.lock().unwrap().push((self.0, Some(tick)));
+        }
+    }
+
+    fn playlist(log: &Log) -> Playlist<Test> {
+        let second = Duration::from_secs(1);
+        Playlist::new()
+            .add(Box::new(Probe(0, None, log.clone())), second)
+            .add(Box::new(Probe(1, Some(second / 2), log.clone())), second)
+            .add(Box::new(Probe(2, None, log.clone())), second)
+    }
+
+    fn run(playlist: &mut Playlist<Test>, until: u64) {
+        assert_eq!(playlist.init(&mut Test), None);
+        for ms in (0..until).step_by(250) {
+            playlist.update(Duration::from_millis(ms), &mut Test);
+        }
+    }
+
+    #[test]
+    fn sequential() {
+        let log = Log::default();
+        run(&mut playlist(&log), 3500);
+        let log = log.lock().unwrap();
+        let expected = [
+            (0, None),
+            (0, Some(0)),
+            (0, Some(250)),
+            (0, Some(500)),
+            (0, Some(750)),
+            (1, None),
+            (1, Some(1000)),
+            (1, Some(1500)),
+            (2, None),
+            (2, Some(2000)),
+            (2, Some(2250)),
+            (2, Some(2500)),
+            (2, Some(2750)),
+            (0, None),
+            (0, Some(3000)),
+            (0, Some(3250)),
+        ];
+        assert_eq!(log.as_slice(), expected);
+    }
+
+    #[test]
+    fn shuffle() {
+        let log = Log::default();
+        run(&mut playlist(&log).shuffle(42), 30000);
+        let entered: Vec<_> = log
+            .lock()
+            .unwrap()
+            .iter()
+            .filter_map(|(id, tick)| tick.is_none().then_some(*id))
+            .collect();
+        assert_eq!(entered.len(), 30);
+        assert!(entered.windows(2).all(|w| w[0] != w[1]));
+        assert!((0..3).all(|id| entered.contains(&id)));
+    }
+
+    #[test]
+    fn empty() {
+        let mut playlist = Playlist::<Test>::new();
+        assert_eq!(playlist.init(&mut Test), Some(Duration::MAX));
+        playlist.update(Duration::ZERO, &mut Test);
+    }
+}
@@ -1,6 +1,13 @@
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec,
+    vec::Vec,
+};
+use core::time::Duration;
 use lux_camp_badge::led::{hsv_rgb_convert::*, Animation, LedMatrix};
 use smart_leds_trait::{SmartLedsWrite, RGB8};
-use std::time::Duration;
 
 struct Inner {
     fading_speed: Option<Duration>,
@@ -9,6 +16,13 @@ struct Inner {
 }
 
 impl Inner {
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("step_size", self.step_size.to_string()),
+            ("fading_speed", format!("{:?}", self.fading_speed)),
+        ]
+    }
+
     fn new(step_size: u8, fading_speed: Option<Duration>) -> Self {
         Self {
             fading_speed,
@@ -42,6 +56,14 @@ where
         self.0.fading_speed
     }
 
+    fn name(&self) -> &str {
+        "rainbow"
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        self.0.params()
+    }
+
     fn update(&mut self, _tick: Duration, matrix: &mut C) {
         self.0.hue += self.0.step_size; // Overflow is what we want here
         let hsv = Hsv8 {
@@ -49,8 +71,9 @@ where
             sat: 255,
             val: 255,
         };
-        let buf = &mut vec![<Hsv8 as Hsv2Rgb>::hsv2rgb(hsv); <C as LedMatrix>::AREA];
-        matrix.set_buf(buf);
+        let mut frame = matrix.frame();
+        frame.as_mut().fill(<Hsv8 as Hsv2Rgb>::hsv2rgb(hsv));
+        matrix.set_buf(frame.as_mut());
     }
 }
 
@@ -78,16 +101,24 @@ where
         self.0.fading_speed
     }
 
+    fn name(&self) -> &str {
+        "rainbow-slide"
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        self.0.params()
+    }
+
     fn update(&mut self, _tick: Duration, matrix: &mut C) {
         self.0.hue += self.0.step_size;
-        let mut buf = Vec::with_capacity(<C as LedMatrix>::AREA);
-        for n in 0..<C as LedMatrix>::AREA {
-            buf.push(<Hsv8 as Hsv2Rgb>::hsv2rgb(Hsv8 {
+        let mut frame = matrix.frame();
+        for (n, pixel) in frame.as_mut().iter_mut().enumerate() {
+            *pixel = <Hsv8 as Hsv2Rgb>::hsv2rgb(Hsv8 {
                 hue: self.0.hue + (n as u8 * self.0.step_size),
                 sat: 255,
                 val: 255,
-            }))
+            })
         }
-        matrix.set_buf(&mut buf);
+        matrix.set_buf(frame.as_mut());
     }
 }
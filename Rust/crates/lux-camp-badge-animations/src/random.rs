@@ -1,7 +1,8 @@
+use alloc::{boxed::Box, format, string::String, vec, vec::Vec};
+use core::time::Duration;
 use lux_camp_badge::led::{Animation, LedMatrix};
 use rand::{rngs::SmallRng, Rng, SeedableRng};
 use smart_leds_trait::{SmartLedsWrite, RGB8};
-use std::time::Duration;
 
 /// Each pixel has a % probability of 30% getting colored randomly on each frame.
 /// The frame rate is choosen at random between 100ms an 1s.
@@ -25,16 +26,20 @@ where
         Some(Duration::from_millis(self.0.gen_range(100..1000)))
     }
 
+    fn name(&self) -> &str {
+        "random"
+    }
+
     fn update(&mut self, _tick: Duration, matrix: &mut C) {
-        let mut buf = Vec::with_capacity(<C as LedMatrix>::AREA);
-        for _ in 0..<C as LedMatrix>::AREA {
-            buf.push(if self.0.gen_bool(0.3) {
+        let mut frame = matrix.frame();
+        for pixel in frame.as_mut() {
+            *pixel = if self.0.gen_bool(0.3) {
                 RGB8::new(self.0.gen(), self.0.gen(), self.0.gen())
             } else {
                 RGB8::new(0, 0, 0)
-            })
+            }
         }
-        matrix.set_buf(&mut buf);
+        matrix.set_buf(frame.as_mut());
     }
 }
 
@@ -73,15 +78,24 @@ where
         Some(Duration::from_secs(1))
     }
 
+    fn name(&self) -> &str {
+        "flip"
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        let RGB8 { r, g, b } = self.color;
+        vec![("color", format!("#{r:02x}{g:02x}{b:02x}"))]
+    }
+
     fn update(&mut self, _tick: Duration, matrix: &mut C) {
-        let mut buf = Vec::with_capacity(<C as LedMatrix>::AREA);
-        for _ in 0..<C as LedMatrix>::AREA {
-            buf.push(if self.rng.gen_bool(0.5) {
+        let mut frame = matrix.frame();
+        for pixel in frame.as_mut() {
+            *pixel = if self.rng.gen_bool(0.5) {
                 self.color
             } else {
                 RGB8::new(0, 0, 0)
-            })
+            }
         }
-        matrix.set_buf(&mut buf);
+        matrix.set_buf(frame.as_mut());
     }
 }
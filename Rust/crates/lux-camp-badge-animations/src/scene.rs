@@ -1,4 +1,4 @@
-use std::time::Duration;
+use core::time::Duration;
 
 use lux_camp_badge::led::{Animation, LedMatrix};
 use smart_leds_trait::{SmartLedsWrite, RGB8};
@@ -28,4 +28,8 @@ where
         }
         Some(Duration::MAX)
     }
+
+    fn name(&self) -> &str {
+        "static"
+    }
 }
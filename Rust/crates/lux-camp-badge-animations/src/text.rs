@@ -0,0 +1,464 @@
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    sync::Arc,
+    vec,
+    vec::Vec,
+};
+use core::time::Duration;
+use lux_camp_badge::led::{hsv_rgb_convert::*, Animation, LedMatrix};
+use smart_leds_trait::{SmartLedsWrite, RGB8};
+
+/// Height of the built-in font in pixels.
+pub const HEIGHT: usize = 5;
+
+/// The pixel columns of `text` in the built-in font, with a blank column between characters.
+/// Bit `0` of each column is the top row. Characters without a glyph are shown as `?`.
+pub fn columns(text: &str) -> Vec<u8> {
+    let mut columns = Vec::new();
+    for (i, c) in text.chars().enumerate() {
+        if i > 0 {
+            columns.push(0);
+        }
+        let (width, rows) = glyph(c);
+        for x in (0..width).rev() {
+            let column = (0..HEIGHT)
+                .filter(|y| rows[*y] >> x & 1 == 1)
+                .fold(0, |column, y| column | 1 << y);
+            columns.push(column);
+        }
+    }
+    columns
+}
+
+/// Width and rows of the glyph of `c`. Bit `0` of each row is its rightmost pixel.
+fn glyph(c: char) -> (u8, [u8; HEIGHT]) {
+    match FONT.binary_search_by_key(&c, |(glyph, ..)| *glyph) {
+        Ok(i) => (FONT[i].1, FONT[i].2),
+        Err(_) if c.is_whitespace() => glyph(' '),
+        Err(_) => glyph('?'),
+    }
+}
+
+/// The text of a [Marquee].
+///
+/// Clones share the same text, so keep a clone to change the text
+/// while the [Marquee] is running on the matrix. Changing it requires `std`.
+#[derive(Clone, Debug, Default)]
+pub struct Text(Shared);
+
+#[cfg(feature = "std")]
+type Shared = Arc<std::sync::Mutex<String>>;
+#[cfg(not(feature = "std"))]
+type Shared = Arc<String>;
+
+impl Text {
+    #[cfg(feature = "std")]
+    pub fn new(text: &str) -> Self {
+        Self(Arc::new(std::sync::Mutex::new(text.into())))
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn new(text: &str) -> Self {
+        Self(Arc::new(text.into()))
+    }
+
+    pub fn get(&self) -> String {
+        self.with(|text| text.into())
+    }
+
+    /// Replace the text. The [Marquee] starts over with the new text.
+    #[cfg(feature = "std")]
+    pub fn set(&self, text: &str) {
+        let mut current = self.0.lock().unwrap_or_else(|err| err.into_inner());
+        current.clear();
+        current.push_str(text);
+    }
+
+    #[cfg(feature = "std")]
+    fn with<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        f(&self.0.lock().unwrap_or_else(|err| err.into_inner()))
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn with<R>(&self, f: impl FnOnce(&str) -> R) -> R {
+        f(&self.0)
+    }
+}
+
+impl From<&str> for Text {
+    fn from(text: &str) -> Self {
+        Self::new(text)
+    }
+}
+
+/// The way the text of a [Marquee] moves.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// From right to left, to be read as usual.
+    Left,
+    Right,
+}
+
+/// The color of the text of a [Marquee].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Fill {
+    Color(RGB8),
+    /// Every column of the text in another color of the rainbow.
+    Rainbow,
+}
+
+/// Text scrolling through the matrix in the built-in 5 pixel font, e.g. your name.
+///
+/// The text enters the matrix on one side and leaves it on the other, then it starts over.
+/// On matrices higher than 5 pixels, it's centered vertically. The top row is at `y` = `0`.
+///
+/// ```ignore
+/// let name = Text::new("Hallo Lux-Camp!");
+/// let marquee = Marquee::new(name.clone()).foreground(Fill::Rainbow).build();
+/// // Later on, e.g. in a request handler
+/// name.set("Grüße");
+/// ```
+pub struct Marquee {
+    text: Text,
+    speed: f32,
+    direction: Direction,
+    foreground: Fill,
+    background: RGB8,
+    /// The text currently shown and its pixel columns.
+    shown: String,
+    columns: Vec<u8>,
+    /// When the current text started scrolling, set by its first update.
+    started: Option<Duration>,
+}
+
+impl Marquee {
+    /// The [Animation::name] of every marquee, e.g. to check whether one is running.
+    pub const NAME: &'static str = "marquee";
+
+    /// White text on black, moving to the left by 8 pixels per second.
+    pub fn new(text: impl Into<Text>) -> Self {
+        Self {
+            text: text.into(),
+            speed: 8.0,
+            direction: Direction::Left,
+            foreground: Fill::Color(RGB8::new(255, 255, 255)),
+            background: RGB8::default(),
+            shown: String::new(),
+            columns: Vec::new(),
+            started: None,
+        }
+    }
+
+    /// Move the text by `pixels_per_second`, at least one pixel every 10 seconds.
+    pub fn speed(mut self, pixels_per_second: f32) -> Self {
+        self.speed = pixels_per_second.max(0.1);
+        self
+    }
+
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+
+    pub fn foreground(mut self, fill: Fill) -> Self {
+        self.foreground = fill;
+        self
+    }
+
+    pub fn background(mut self, color: RGB8) -> Self {
+        self.background = color;
+        self
+    }
+
+    pub fn build<Matrix, Driver>(self) -> Box<dyn Animation<Matrix> + Send>
+    where
+        Matrix: LedMatrix<Driver = Driver>,
+        Driver: SmartLedsWrite<Color = RGB8>,
+    {
+        Box::new(self)
+    }
+
+    /// The time it takes the text to move by one pixel.
+    fn step(&self) -> Duration {
+        Duration::from_micros((1_000_000.0 / self.speed) as u64)
+    }
+}
+
+impl<B, C: LedMatrix<Driver = B>> Animation<C> for Marquee
+where
+    B: SmartLedsWrite<Color = RGB8>,
+{
+    fn init(&mut self, _matrix: &mut C) -> Option<Duration> {
+        self.started = None;
+        Some(self.step())
+    }
+
+    fn update(&mut self, tick: Duration, matrix: &mut C) {
+        if self.text.with(|text| text != self.shown) {
+            self.shown = self.text.get();
+            self.columns = columns(&self.shown);
+            self.started = None;
+        }
+        let started = *self.started.get_or_insert(tick);
+        let steps = tick.saturating_sub(started).as_micros() / self.step().as_micros().max(1);
+
+        // The text follows a blank matrix width, so it enters the matrix from its edge
+        let width = <C as LedMatrix>::X;
+        let length = self.columns.len() + width;
+        let position = (steps % length as u128) as usize;
+        let top = <C as LedMatrix>::Y.saturating_sub(HEIGHT) / 2;
+        for x in 0..width {
+            let index = match self.direction {
+                Direction::Left => (position + x) % length,
+                Direction::Right => (length - position + x) % length,
+            };
+            let column = index.checked_sub(width).map_or(0, |i| self.columns[i]);
+            for y in 0..<C as LedMatrix>::Y {
+                let lit = y
+                    .checked_sub(top)
+                    .is_some_and(|row| row < HEIGHT && column >> row & 1 == 1);
+                let color = match (lit, self.foreground) {
+                    (false, _) => self.background,
+                    (true, Fill::Color(color)) => color,
+                    (true, Fill::Rainbow) => <Hsv8 as Hsv2Rgb>::hsv2rgb(Hsv8 {
+                        hue: (index * 16) as u8,
+                        sat: 255,
+                        val: 255,
+                    }),
+                };
+                matrix.set_2d(x, y, &color);
+            }
+        }
+    }
+
+    fn name(&self) -> &str {
+        Self::NAME
+    }
+
+    fn params(&self) -> Vec<(&'static str, String)> {
+        vec![
+            ("text", self.text.get()),
+            ("speed", self.speed.to_string()),
+            ("direction", format!("{:?}", self.direction)),
+            ("foreground", format!("{:?}", self.foreground)),
+            ("background", format!("{:?}", self.background)),
+        ]
+    }
+}
+
+/// Glyphs of the font, sorted by character: width and rows, top row first.
+#[rustfmt::skip]
+const FONT: &[(char, u8, [u8; HEIGHT])] = &[
+    (' ', 2, [0b00, 0b00, 0b00, 0b00, 0b00]),
+    ('!', 1, [0b1, 0b1, 0b1, 0b0, 0b1]),
+    ('"', 3, [0b101, 0b101, 0b000, 0b000, 0b000]),
+    ('#', 5, [0b01010, 0b11111, 0b01010, 0b11111, 0b01010]),
+    ('$', 3, [0b011, 0b110, 0b010, 0b011, 0b110]),
+    ('%', 3, [0b101, 0b001, 0b010, 0b100, 0b101]),
+    ('&', 4, [0b0100, 0b1010, 0b0100, 0b1010, 0b0101]),
+    ('\'', 1, [0b1, 0b1, 0b0, 0b0, 0b0]),
+    ('(', 2, [0b01, 0b10, 0b10, 0b10, 0b01]),
+    (')', 2, [0b10, 0b01, 0b01, 0b01, 0b10]),
+    ('*', 3, [0b000, 0b101, 0b010, 0b101, 0b000]),
+    ('+', 3, [0b000, 0b010, 0b111, 0b010, 0b000]),
+    (',', 2, [0b00, 0b00, 0b00, 0b01, 0b10]),
+    ('-', 3, [0b000, 0b000, 0b111, 0b000, 0b000]),
+    ('.', 1, [0b0, 0b0, 0b0, 0b0, 0b1]),
+    ('/', 3, [0b001, 0b001, 0b010, 0b100, 0b100]),
+    ('0', 3, [0b111, 0b101, 0b101, 0b101, 0b111]),
+    ('1', 3, [0b010, 0b110, 0b010, 0b010, 0b111]),
+    ('2', 3, [0b111, 0b001, 0b111, 0b100, 0b111]),
+    ('3', 3, [0b111, 0b001, 0b011, 0b001, 0b111]),
+    ('4', 3, [0b101, 0b101, 0b111, 0b001, 0b001]),
+    ('5', 3, [0b111, 0b100, 0b111, 0b001, 0b111]),
+    ('6', 3, [0b111, 0b100, 0b111, 0b101, 0b111]),
+    ('7', 3, [0b111, 0b001, 0b001, 0b010, 0b010]),
+    ('8', 3, [0b111, 0b101, 0b111, 0b101, 0b111]),
+    ('9', 3, [0b111, 0b101, 0b111, 0b001, 0b111]),
+    (':', 1, [0b0, 0b1, 0b0, 0b1, 0b0]),
+    (';', 2, [0b00, 0b01, 0b00, 0b01, 0b10]),
+    ('<', 3, [0b001, 0b010, 0b100, 0b010, 0b001]),
+    ('=', 3, [0b000, 0b111, 0b000, 0b111, 0b000]),
+    ('>', 3, [0b100, 0b010, 0b001, 0b010, 0b100]),
+    ('?', 3, [0b111, 0b001, 0b011, 0b000, 0b010]),
+    ('@', 4, [0b0110, 0b1001, 0b1011, 0b1000, 0b0111]),
+    ('A', 3, [0b010, 0b101, 0b111, 0b101, 0b101]),
+    ('B', 3, [0b110, 0b101, 0b110, 0b101, 0b110]),
+    ('C', 3, [0b011, 0b100, 0b100, 0b100, 0b011]),
+    ('D', 3, [0b110, 0b101, 0b101, 0b101, 0b110]),
+    ('E', 3, [0b111, 0b100, 0b110, 0b100, 0b111]),
+    ('F', 3, [0b111, 0b100, 0b110, 0b100, 0b100]),
+    ('G', 3, [0b011, 0b100, 0b101, 0b101, 0b011]),
+    ('H', 3, [0b101, 0b101, 0b111, 0b101, 0b101]),
+    ('I', 3, [0b111, 0b010, 0b010, 0b010, 0b111]),
+    ('J', 3, [0b001, 0b001, 0b001, 0b101, 0b010]),
+    ('K', 3, [0b101, 0b101, 0b110, 0b101, 0b101]),
+    ('L', 3, [0b100, 0b100, 0b100, 0b100, 0b111]),
+    ('M', 5, [0b10001, 0b11011, 0b10101, 0b10001, 0b10001]),
+    ('N', 4, [0b1001, 0b1101, 0b1011, 0b1001, 0b1001]),
+    ('O', 3, [0b010, 0b101, 0b101, 0b101, 0b010]),
+    ('P', 3, [0b110, 0b101, 0b110, 0b100, 0b100]),
+    ('Q', 4, [0b0110, 0b1001, 0b1001, 0b1010, 0b0101]),
+    ('R', 3, [0b110, 0b101, 0b110, 0b101, 0b101]),
+    ('S', 3, [0b011, 0b100, 0b010, 0b001, 0b110]),
+    ('T', 3, [0b111, 0b010, 0b010, 0b010, 0b010]),
+    ('U', 3, [0b101, 0b101, 0b101, 0b101, 0b111]),
+    ('V', 3, [0b101, 0b101, 0b101, 0b101, 0b010]),
+    ('W', 5, [0b10001, 0b10001, 0b10101, 0b11011, 0b10001]),
+    ('X', 3, [0b101, 0b101, 0b010, 0b101, 0b101]),
+    ('Y', 3, [0b101, 0b101, 0b010, 0b010, 0b010]),
+    ('Z', 3, [0b111, 0b001, 0b010, 0b100, 0b111]),
+    ('[', 2, [0b11, 0b10, 0b10, 0b10, 0b11]),
+    ('\\', 3, [0b100, 0b100, 0b010, 0b001, 0b001]),
+    (']', 2, [0b11, 0b01, 0b01, 0b01, 0b11]),
+    ('^', 3, [0b010, 0b101, 0b000, 0b000, 0b000]),
+    ('_', 3, [0b000, 0b000, 0b000, 0b000, 0b111]),
+    ('`', 2, [0b10, 0b01, 0b00, 0b00, 0b00]),
+    ('a', 3, [0b000, 0b011, 0b101, 0b101, 0b011]),
+    ('b', 3, [0b100, 0b110, 0b101, 0b101, 0b110]),
+    ('c', 3, [0b000, 0b011, 0b100, 0b100, 0b011]),
+    ('d', 3, [0b001, 0b011, 0b101, 0b101, 0b011]),
+    ('e', 3, [0b000, 0b010, 0b111, 0b100, 0b011]),
+    ('f', 3, [0b011, 0b100, 0b110, 0b100, 0b100]),
+    ('g', 3, [0b011, 0b101, 0b011, 0b001, 0b110]),
+    ('h', 3, [0b100, 0b110, 0b101, 0b101, 0b101]),
+    ('i', 1, [0b1, 0b0, 0b1, 0b1, 0b1]),
+    ('j', 2, [0b01, 0b00, 0b01, 0b01, 0b10]),
+    ('k', 3, [0b100, 0b101, 0b110, 0b101, 0b101]),
+    ('l', 2, [0b10, 0b10, 0b10, 0b10, 0b01]),
+    ('m', 5, [0b00000, 0b11010, 0b10101, 0b10101, 0b10101]),
+    ('n', 3, [0b000, 0b110, 0b101, 0b101, 0b101]),
+    ('o', 3, [0b000, 0b010, 0b101, 0b101, 0b010]),
+    ('p', 3, [0b000, 0b110, 0b101, 0b110, 0b100]),
+    ('q', 3, [0b000, 0b011, 0b101, 0b011, 0b001]),
+    ('r', 3, [0b000, 0b011, 0b100, 0b100, 0b100]),
+    ('s', 3, [0b000, 0b011, 0b110, 0b011, 0b110]),
+    ('t', 3, [0b010, 0b111, 0b010, 0b010, 0b001]),
+    ('u', 3, [0b000, 0b101, 0b101, 0b101, 0b011]),
+    ('v', 3, [0b000, 0b101, 0b101, 0b101, 0b010]),
+    ('w', 5, [0b00000, 0b10001, 0b10101, 0b10101, 0b01010]),
+    ('x', 3, [0b000, 0b101, 0b010, 0b010, 0b101]),
+    ('y', 3, [0b000, 0b101, 0b011, 0b001, 0b110]),
+    ('z', 3, [0b000, 0b111, 0b001, 0b010, 0b111]),
+    ('{', 3, [0b011, 0b010, 0b110, 0b010, 0b011]),
+    ('|', 1, [0b1, 0b1, 0b1, 0b1, 0b1]),
+    ('}', 3, [0b110, 0b010, 0b011, 0b010, 0b110]),
+    ('~', 4, [0b0000, 0b0101, 0b1010, 0b0000, 0b0000]),
+    ('Ä', 3, [0b101, 0b010, 0b101, 0b111, 0b101]),
+    ('Ö', 3, [0b101, 0b000, 0b111, 0b101, 0b111]),
+    ('Ü', 3, [0b101, 0b000, 0b101, 0b101, 0b111]),
+    ('ß', 3, [0b010, 0b101, 0b110, 0b101, 0b110]),
+    ('ä', 3, [0b101, 0b000, 0b011, 0b101, 0b011]),
+    ('ö', 3, [0b101, 0b000, 0b010, 0b101, 0b010]),
+    ('ü', 3, [0b101, 0b000, 0b101, 0b101, 0b011]),
+];
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::TestDriver;
+    use lux_camp_badge::led::framebuffer::FrameBuffer;
+
+    type Badge = FrameBuffer<TestDriver, 5, 5>;
+
+    /// The columns of the matrix, bit `0` being the top row.
+    fn shown(matrix: &Badge, color: RGB8) -> Vec<u8> {
+        (0..5)
+            .map(|x| {
+                (0..5)
+                    .filter(|y| matrix.get_2d(x, *y) == Some(&color))
+                    .fold(0, |column, y| column | 1 << y)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn font() {
+        assert!(FONT.windows(2).all(|pair| pair[0].0 < pair[1].0));
+        assert_eq!(FONT.len(), 95 + 7);
+        for (i, (c, width, rows)) in FONT.iter().enumerate() {
+            assert!(rows.iter().all(|row| row >> width == 0), "{c} too wide");
+            let twins = FONT[..i].iter().filter(|glyph| glyph.2 == *rows);
+            assert_eq!(twins.count(), 0, "{c} looks like another glyph");
+        }
+
+        assert_eq!(columns("Hi"), [0b11111, 0b00100, 0b11111, 0, 0b11101]);
+        assert_eq!(columns("\t"), columns(" "));
+        assert_eq!(columns("€"), columns("?"));
+        assert_eq!(columns("Ü").len(), 3);
+        assert!(columns("").is_empty());
+    }
+
+    #[test]
+    fn scroll() {
+        let white = RGB8::new(255, 255, 255);
+        let mut matrix = Badge::new();
+        let mut marquee = Marquee::new("T").speed(10.0).build();
+        assert_eq!(marquee.init(&mut matrix), Some(Duration::from_millis(100)));
+
+        let mut frames = Vec::new();
+        for step in 0..10 {
+            marquee.update(Duration::from_millis(1000 + step * 100), &mut matrix);
+            frames.push(shown(&matrix, white));
+        }
+        // The text enters from the right, leaves on the left and starts over
+        assert_eq!(frames[0], [0; 5]);
+        assert_eq!(frames[1], [0, 0, 0, 0, 0b00001]);
+        assert_eq!(frames[3], [0, 0, 0b00001, 0b11111, 0b00001]);
+        assert_eq!(frames[7], [0b00001, 0, 0, 0, 0]);
+        assert_eq!(frames[8], [0; 5]);
+        assert_eq!(frames[9], frames[1]);
+    }
+
+    #[test]
+    fn direction_and_colors() {
+        let (red, blue) = (RGB8::new(255, 0, 0), RGB8::new(0, 0, 255));
+        let mut matrix = Badge::new();
+        let mut marquee = Marquee::new("T")
+            .direction(Direction::Right)
+            .foreground(Fill::Color(red))
+            .background(blue)
+            .build();
+        marquee.init(&mut matrix);
+        marquee.update(Duration::ZERO, &mut matrix);
+        assert!(matrix.read_buf().iter().all(|pixel| *pixel == blue));
+
+        // The text enters from the left
+        marquee.update(Duration::from_millis(250), &mut matrix);
+        assert_eq!(shown(&matrix, red), [0b11111, 0b00001, 0, 0, 0]);
+        assert_eq!(matrix.read_buf().iter().filter(|p| **p == blue).count(), 19);
+
+        let mut rainbow = Marquee::new("I").foreground(Fill::Rainbow).build();
+        rainbow.init(&mut matrix);
+        rainbow.update(Duration::ZERO, &mut matrix);
+        rainbow.update(Duration::from_millis(500), &mut matrix);
+        let colors: Vec<_> = (1..4).map(|x| matrix.get_2d(x, 0).copied()).collect();
+        assert!(colors.iter().all(|color| *color != Some(RGB8::default())));
+        assert_ne!(colors[0], colors[1]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn update_text() {
+        let text = Text::new("ab");
+        let mut matrix = FrameBuffer::<TestDriver, 5, 7>::new();
+        let mut marquee = Marquee::new(text.clone()).speed(1.0).build();
+        marquee.init(&mut matrix);
+        marquee.update(Duration::ZERO, &mut matrix);
+        assert_eq!(marquee.params()[0], ("text", "ab".to_string()));
+
+        // A new text starts over, whatever the time
+        text.set("|");
+        marquee.update(Duration::from_secs(3), &mut matrix);
+        marquee.update(Duration::from_secs(4), &mut matrix);
+        assert_eq!(marquee.params()[0], ("text", "|".to_string()));
+        let white = RGB8::new(255, 255, 255);
+        // Centered vertically on the 7 rows
+        let lit: Vec<_> = (0..7)
+            .map(|y| matrix.get_2d(4, y) == Some(&white))
+            .collect();
+        assert_eq!(lit, [false, true, true, true, true, true, false]);
+    }
+}
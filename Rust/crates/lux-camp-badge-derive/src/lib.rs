@@ -0,0 +1,123 @@
+//! Derive macros of the `lux-camp-badge` crate, re-exported from there.
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, spanned::Spanned, Data, DeriveInput, Error, Field, Index, Member};
+
+/// Implement `LedMatrix` for a wrapper around a type which already implements it,
+/// e.g. a `FrameBuffer`. Every item of the trait is forwarded to the wrapped field.
+///
+/// The wrapped field is the only field of the struct, or the one marked with `#[led_matrix]`:
+///
+/// ```ignore
+/// #[derive(Default, LedMatrix)]
+/// struct LuxBadge(FrameBuffer<Ws2812Esp32Rmt, 5, 5, FlipX<Progressive>>);
+///
+/// #[derive(LedMatrix)]
+/// struct Board {
+///     #[led_matrix]
+///     leds: FrameBuffer<Ws2812Esp32Rmt, 8, 8, Serpentine>,
+///     name: &'static str,
+/// }
+/// ```
+#[proc_macro_derive(LedMatrix, attributes(led_matrix))]
+pub fn derive_led_matrix(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    led_matrix(input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn led_matrix(input: DeriveInput) -> Result<TokenStream2, Error> {
+    let Data::Struct(data) = &input.data else {
+        return Err(Error::new(
+            input.span(),
+            "LedMatrix can only be derived for structs",
+        ));
+    };
+    let fields: Vec<_> = data.fields.iter().enumerate().collect();
+    let marked: Vec<_> = fields
+        .iter()
+        .filter(|(_, field)| {
+            field
+                .attrs
+                .iter()
+                .any(|attr| attr.path().is_ident("led_matrix"))
+        })
+        .collect();
+    let (index, field): (usize, &Field) = match (marked.as_slice(), fields.as_slice()) {
+        ([(index, field)], _) | ([], [(index, field)]) => (*index, field),
+        _ => {
+            return Err(Error::new(
+                input.span(),
+                "expected a single field or exactly one field marked with #[led_matrix]",
+            ))
+        }
+    };
+    let member = match &field.ident {
+        Some(ident) => Member::Named(ident.clone()),
+        None => Member::Unnamed(Index::from(index)),
+    };
+
+    let name = &input.ident;
+    let inner = &field.ty;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let led = quote!(::lux_camp_badge::led);
+    Ok(quote! {
+        impl #impl_generics #led::LedMatrix for #name #ty_generics #where_clause {
+            const X: usize = <#inner as #led::LedMatrix>::X;
+            const Y: usize = <#inner as #led::LedMatrix>::Y;
+            const Z: usize = <#inner as #led::LedMatrix>::Z;
+            const AREA: usize = <#inner as #led::LedMatrix>::AREA;
+            const VOLUME: usize = <#inner as #led::LedMatrix>::VOLUME;
+            const LEDS: usize = <#inner as #led::LedMatrix>::LEDS;
+            type Driver = <#inner as #led::LedMatrix>::Driver;
+            type Frame = <#inner as #led::LedMatrix>::Frame;
+
+            fn frame(&self) -> Self::Frame {
+                #led::LedMatrix::frame(&self.#member)
+            }
+
+            fn read_buf(&self) -> &[#led::Color<Self>]
+            where
+                #led::Color<Self>: #led::Dimmable,
+            {
+                #led::LedMatrix::read_buf(&self.#member)
+            }
+
+            fn set_buf(&mut self, buf: &mut [#led::Color<Self>]) {
+                #led::LedMatrix::set_buf(&mut self.#member, buf)
+            }
+
+            fn set_2d(&mut self, x: usize, y: usize, color: &#led::Color<Self>) {
+                #led::LedMatrix::set_2d(&mut self.#member, x, y, color)
+            }
+
+            fn set_3d(&mut self, x: usize, y: usize, z: usize, color: &#led::Color<Self>) {
+                #led::LedMatrix::set_3d(&mut self.#member, x, y, z, color)
+            }
+
+            fn index_2d(x: usize, y: usize) -> usize {
+                <#inner as #led::LedMatrix>::index_2d(x, y)
+            }
+
+            fn index_3d(x: usize, y: usize, z: usize) -> usize {
+                <#inner as #led::LedMatrix>::index_3d(x, y, z)
+            }
+
+            fn get_2d(&self, x: usize, y: usize) -> Option<&#led::Color<Self>>
+            where
+                #led::Color<Self>: #led::Dimmable,
+            {
+                #led::LedMatrix::get_2d(&self.#member, x, y)
+            }
+
+            fn get_3d(&self, x: usize, y: usize, z: usize) -> Option<&#led::Color<Self>>
+            where
+                #led::Color<Self>: #led::Dimmable,
+            {
+                #led::LedMatrix::get_3d(&self.#member, x, y, z)
+            }
+        }
+    })
+}
@@ -0,0 +1,106 @@
+//! Time sources for the LED matrix.
+//!
+//! The [Matrix](super::matrix::Matrix) schedules frames according to a [Clock],
+//! which can be set via [MatrixBuilder::clock](super::matrix::MatrixBuilder::clock).
+//! By default, the system time of the ESP is used with the `esp` feature and [StdClock] otherwise.
+//! Without `std`, there is no default clock: the matrix goes by the time passed to
+//! [Matrix::tick](super::matrix::Matrix::tick).
+use alloc::boxed::Box;
+use core::time::Duration;
+#[cfg(feature = "std")]
+use std::{
+    sync::{Arc, Mutex},
+    time::Instant,
+};
+
+/// A monotonic time source.
+pub trait Clock: Send {
+    /// The current time, measured from some fixed point in the past.
+    fn now(&self) -> Duration;
+}
+
+/// The system time of the ESP, i.e. the time since boot.
+#[cfg(feature = "esp")]
+#[derive(Clone, Copy, Default)]
+pub struct EspClock;
+
+#[cfg(feature = "esp")]
+impl Clock for EspClock {
+    fn now(&self) -> Duration {
+        esp_idf_svc::systime::EspSystemTime {}.now()
+    }
+}
+
+/// Clock based on [Instant], measuring the time since its creation.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy)]
+pub struct StdClock(Instant);
+
+#[cfg(feature = "std")]
+impl Default for StdClock {
+    fn default() -> Self {
+        Self(Instant::now())
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    fn now(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+/// Clock that only moves when told so. Useful for deterministic tests.
+///
+/// Clones share the same time, so a clone can be handed to the matrix
+/// while the original is used to advance the time.
+#[cfg(feature = "std")]
+#[derive(Clone, Default)]
+pub struct MockClock(Arc<Mutex<Duration>>);
+
+#[cfg(feature = "std")]
+impl MockClock {
+    /// Create a new clock starting at `now`.
+    pub fn new(now: Duration) -> Self {
+        Self(Arc::new(Mutex::new(now)))
+    }
+
+    /// Move the time forward by `step`.
+    pub fn advance(&self, step: Duration) {
+        if let Ok(mut now) = self.0.lock() {
+            *now = now.saturating_add(step);
+        }
+    }
+
+    /// Set the time to `now`.
+    pub fn set(&self, now: Duration) {
+        if let Ok(mut time) = self.0.lock() {
+            *time = now;
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Clock for MockClock {
+    fn now(&self) -> Duration {
+        self.0.lock().map(|now| *now).unwrap_or_default()
+    }
+}
+
+/// The clock used if none was set explicitly.
+#[cfg(feature = "esp")]
+pub(crate) fn default() -> Option<Box<dyn Clock>> {
+    Some(Box::new(EspClock))
+}
+
+/// The clock used if none was set explicitly.
+#[cfg(all(feature = "std", not(feature = "esp")))]
+pub(crate) fn default() -> Option<Box<dyn Clock>> {
+    Some(Box::<StdClock>::default())
+}
+
+/// The clock used if none was set explicitly.
+#[cfg(not(feature = "std"))]
+pub(crate) fn default() -> Option<Box<dyn Clock>> {
+    None
+}
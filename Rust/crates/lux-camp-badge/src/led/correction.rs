@@ -0,0 +1,147 @@
+//! Output corrections applied to every pixel before it is written to the LEDs.
+//!
+//! The brightness perceived by our eyes is not linear to the PWM duty cycle of the LEDs,
+//! and the color channels of cheap LEDs are not balanced.
+//! Without correction, dark colors appear washed out and white looks blue-ish.
+//!
+//! The presets are the ones of FastLED's `ColorCorrection` and `ColorTemperature`.
+use smart_leds_trait::RGB8;
+
+use super::{hsv_rgb_convert::Hsv8, scale8};
+
+/// Typical values for SMD5050 LEDs.
+pub const TYPICAL_SMD5050: RGB8 = RGB8::new(255, 176, 240);
+/// Typical values for generic LED strips (e.g. WS2812).
+pub const TYPICAL_LED_STRIP: RGB8 = RGB8::new(255, 176, 240);
+/// Typical values for 8mm "pixels on a string".
+pub const TYPICAL_PIXEL_STRING: RGB8 = RGB8::new(255, 224, 140);
+/// Uncorrected color / temperature.
+pub const UNCORRECTED: RGB8 = RGB8::new(255, 255, 255);
+
+/// Color temperature of a candle (1900 Kelvin).
+pub const CANDLE: RGB8 = RGB8::new(255, 147, 41);
+/// Color temperature of a 40W tungsten light bulb (2600 Kelvin).
+pub const TUNGSTEN_40W: RGB8 = RGB8::new(255, 197, 143);
+/// Color temperature of a 100W tungsten light bulb (2850 Kelvin).
+pub const TUNGSTEN_100W: RGB8 = RGB8::new(255, 214, 170);
+/// Color temperature of a halogen light bulb (3200 Kelvin).
+pub const HALOGEN: RGB8 = RGB8::new(255, 241, 224);
+/// Color temperature of the sun at high noon (5400 Kelvin).
+pub const HIGH_NOON_SUN: RGB8 = RGB8::new(255, 255, 251);
+/// Color temperature of an overcast sky (7000 Kelvin).
+pub const OVERCAST_SKY: RGB8 = RGB8::new(201, 226, 255);
+/// Color temperature of a clear blue sky (20000 Kelvin).
+pub const CLEAR_BLUE_SKY: RGB8 = RGB8::new(64, 156, 255);
+
+/// Gamma and color correction stage, see [MatrixBuilder::correction](super::matrix::MatrixBuilder::correction).
+#[derive(Clone)]
+pub struct Correction {
+    gamma: [u8; 256],
+    color: RGB8,
+    temperature: RGB8,
+}
+
+impl Default for Correction {
+    /// A correction that doesn't change anything.
+    fn default() -> Self {
+        Self {
+            gamma: core::array::from_fn(|i| i as u8),
+            color: UNCORRECTED,
+            temperature: UNCORRECTED,
+        }
+    }
+}
+
+impl Correction {
+    /// Start with a correction that doesn't change anything.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply the given `gamma` to each channel. A value of around `2.2` to `2.8`
+    /// suits most WS2812 LEDs, `1.0` disables the gamma correction.
+    pub fn gamma(mut self, gamma: f32) -> Self {
+        self.gamma = core::array::from_fn(|i| {
+            (libm::powf(i as f32 / 255.0, gamma.max(0.0)) * 255.0 + 0.5) as u8
+        });
+        self
+    }
+
+    /// Scale the color channels to balance the LEDs, e.g. [TYPICAL_LED_STRIP].
+    pub fn color(mut self, correction: RGB8) -> Self {
+        self.color = correction;
+        self
+    }
+
+    /// Tint the output to a color temperature, e.g. [TUNGSTEN_100W].
+    pub fn temperature(mut self, temperature: RGB8) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// The gamma corrected `value`.
+    pub fn gamma_of(&self, value: u8) -> u8 {
+        self.gamma[value as usize]
+    }
+
+    /// The combined scale of the color correction and temperature for each channel.
+    pub fn scale(&self) -> RGB8 {
+        RGB8::new(
+            scale8(self.color.r, self.temperature.r),
+            scale8(self.color.g, self.temperature.g),
+            scale8(self.color.b, self.temperature.b),
+        )
+    }
+}
+
+/// RGB or HSV values can be corrected for the output to the LEDs.
+pub trait Correctable {
+    /// Apply the `correction` to `self`.
+    #[allow(unused)]
+    fn correct(&mut self, correction: &Correction) {}
+}
+
+impl Correctable for RGB8 {
+    fn correct(&mut self, correction: &Correction) {
+        let scale = correction.scale();
+        self.r = scale8(correction.gamma_of(self.r), scale.r);
+        self.g = scale8(correction.gamma_of(self.g), scale.g);
+        self.b = scale8(correction.gamma_of(self.b), scale.b);
+    }
+}
+
+/// Only the gamma correction applies to HSV values.
+impl Correctable for Hsv8 {
+    fn correct(&mut self, correction: &Correction) {
+        self.val = correction.gamma_of(self.val);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn gamma() {
+        let linear = Correction::new().gamma(1.0);
+        assert!((0..=255).all(|v| linear.gamma_of(v) == v));
+
+        let gamma = Correction::new().gamma(2.2);
+        assert_eq!(gamma.gamma_of(0), 0);
+        assert_eq!(gamma.gamma_of(255), 255);
+        assert!(gamma.gamma_of(128) < 64);
+        assert!((1..=255).all(|v| gamma.gamma_of(v - 1) <= gamma.gamma_of(v)));
+    }
+
+    #[test]
+    fn color() {
+        let correction = Correction::new().color(TYPICAL_LED_STRIP);
+        let mut white = RGB8::new(255, 255, 255);
+        white.correct(&correction);
+        assert_eq!(white, TYPICAL_LED_STRIP);
+
+        let mut pixel = RGB8::new(10, 20, 30);
+        pixel.correct(&Correction::new());
+        assert_eq!(pixel, RGB8::new(10, 20, 30));
+    }
+}
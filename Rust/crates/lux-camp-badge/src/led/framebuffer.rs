@@ -0,0 +1,342 @@
+//! A ready-made [LedMatrix] for any driver and [Layout].
+//!
+//! Instead of implementing [LedMatrix] by hand, a new board is a type alias:
+//!
+//! ```ignore
+//! type LuxBadge = FrameBuffer<Ws2812Esp32Rmt, 5, 5, FlipX<Progressive>>;
+//! ```
+//!
+//! LED cubes use a [CubeBuffer] instead.
+//!
+//! Wrappers around a [FrameBuffer], e.g. to implement further traits on them,
+//! can derive [LedMatrix](macro@super::LedMatrix).
+//!
+//! Both are sized at compile time and don't allocate. Each is its own [LedMatrix::Frame].
+use core::{array, marker::PhantomData};
+
+use smart_leds_trait::SmartLedsWrite;
+
+use super::{
+    layout::{Cube, Layout, Layout3d, Progressive},
+    Color, Dimmable, LedMatrix,
+};
+
+/// Frame buffer of a matrix with `X` x `Y` LEDs, wired according to the [Layout] `L`.
+///
+/// The pixels are stored in the order of the LEDs on the strip.
+pub struct FrameBuffer<D: SmartLedsWrite, const X: usize, const Y: usize, L = Progressive> {
+    pixels: [[D::Color; X]; Y],
+    layout: PhantomData<fn() -> (D, L)>,
+}
+
+impl<D, const X: usize, const Y: usize, L> FrameBuffer<D, X, Y, L>
+where
+    D: SmartLedsWrite,
+    D::Color: Clone + Default,
+{
+    /// A frame buffer with all pixels off.
+    pub fn new() -> Self {
+        Self {
+            pixels: array::from_fn(|_| array::from_fn(|_| D::Color::default())),
+            layout: PhantomData,
+        }
+    }
+}
+
+impl<D, const X: usize, const Y: usize, L> Default for FrameBuffer<D, X, Y, L>
+where
+    D: SmartLedsWrite,
+    D::Color: Clone + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D, const X: usize, const Y: usize, L> Clone for FrameBuffer<D, X, Y, L>
+where
+    D: SmartLedsWrite,
+    D::Color: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pixels: self.pixels.clone(),
+            layout: PhantomData,
+        }
+    }
+}
+
+impl<D, const X: usize, const Y: usize, L> LedMatrix for FrameBuffer<D, X, Y, L>
+where
+    D: SmartLedsWrite,
+    D::Color: Clone,
+    L: Layout,
+{
+    const X: usize = X;
+    const Y: usize = Y;
+    type Driver = D;
+    type Frame = Self;
+
+    fn frame(&self) -> Self {
+        self.clone()
+    }
+
+    fn read_buf(&self) -> &[Color<Self>]
+    where
+        Color<Self>: Dimmable,
+    {
+        self.as_ref()
+    }
+
+    fn set_buf(&mut self, buf: &mut [Color<Self>]) {
+        // A guard just in case, as clone_from_slice would panic
+        if buf.len() == X * Y {
+            self.as_mut().clone_from_slice(buf);
+        }
+    }
+
+    fn set_2d(&mut self, x: usize, y: usize, color: &Color<Self>) {
+        if x < X && y < Y {
+            self.as_mut()[Self::index_2d(x, y)] = color.clone();
+        }
+    }
+
+    fn index_2d(x: usize, y: usize) -> usize {
+        L::index(x, y, X, Y)
+    }
+}
+
+/// Frame buffer of a cube with `X` x `Y` x `Z` LEDs, wired according to the [Layout3d] `L`.
+///
+/// The pixels are stored in the order of the LEDs on the strip.
+/// Writing to `x` / `y` via [LedMatrix::set_2d] draws the bottom layer (`z` = 0).
+pub struct CubeBuffer<
+    D: SmartLedsWrite,
+    const X: usize,
+    const Y: usize,
+    const Z: usize,
+    L = Cube<Progressive>,
+> {
+    pixels: [[[D::Color; X]; Y]; Z],
+    layout: PhantomData<fn() -> (D, L)>,
+}
+
+impl<D, const X: usize, const Y: usize, const Z: usize, L> CubeBuffer<D, X, Y, Z, L>
+where
+    D: SmartLedsWrite,
+    D::Color: Clone + Default,
+{
+    /// A frame buffer with all pixels off.
+    pub fn new() -> Self {
+        Self {
+            pixels: array::from_fn(|_| array::from_fn(|_| array::from_fn(|_| D::Color::default()))),
+            layout: PhantomData,
+        }
+    }
+}
+
+impl<D, const X: usize, const Y: usize, const Z: usize, L> Default for CubeBuffer<D, X, Y, Z, L>
+where
+    D: SmartLedsWrite,
+    D::Color: Clone + Default,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<D, const X: usize, const Y: usize, const Z: usize, L> Clone for CubeBuffer<D, X, Y, Z, L>
+where
+    D: SmartLedsWrite,
+    D::Color: Clone,
+{
+    fn clone(&self) -> Self {
+        Self {
+            pixels: self.pixels.clone(),
+            layout: PhantomData,
+        }
+    }
+}
+
+impl<D, const X: usize, const Y: usize, const Z: usize, L> LedMatrix for CubeBuffer<D, X, Y, Z, L>
+where
+    D: SmartLedsWrite,
+    D::Color: Clone,
+    L: Layout3d,
+{
+    const X: usize = X;
+    const Y: usize = Y;
+    const Z: usize = Z;
+    type Driver = D;
+    type Frame = Self;
+
+    fn frame(&self) -> Self {
+        self.clone()
+    }
+
+    fn read_buf(&self) -> &[Color<Self>]
+    where
+        Color<Self>: Dimmable,
+    {
+        self.as_ref()
+    }
+
+    fn set_buf(&mut self, buf: &mut [Color<Self>]) {
+        if buf.len() == X * Y * Z {
+            self.as_mut().clone_from_slice(buf);
+        }
+    }
+
+    fn set_2d(&mut self, x: usize, y: usize, color: &Color<Self>) {
+        self.set_3d(x, y, 0, color);
+    }
+
+    fn set_3d(&mut self, x: usize, y: usize, z: usize, color: &Color<Self>) {
+        if x < X && y < Y && z < Z {
+            self.as_mut()[Self::index_3d(x, y, z)] = color.clone();
+        }
+    }
+
+    fn index_2d(x: usize, y: usize) -> usize {
+        Self::index_3d(x, y, 0)
+    }
+
+    fn index_3d(x: usize, y: usize, z: usize) -> usize {
+        L::index(x, y, z, X, Y, Z)
+    }
+}
+
+impl<D, const X: usize, const Y: usize, L> AsRef<[D::Color]> for FrameBuffer<D, X, Y, L>
+where
+    D: SmartLedsWrite,
+{
+    fn as_ref(&self) -> &[D::Color] {
+        flatten(&self.pixels)
+    }
+}
+
+impl<D, const X: usize, const Y: usize, L> AsMut<[D::Color]> for FrameBuffer<D, X, Y, L>
+where
+    D: SmartLedsWrite,
+{
+    fn as_mut(&mut self) -> &mut [D::Color] {
+        flatten_mut(&mut self.pixels)
+    }
+}
+
+impl<D, const X: usize, const Y: usize, const Z: usize, L> AsRef<[D::Color]>
+    for CubeBuffer<D, X, Y, Z, L>
+where
+    D: SmartLedsWrite,
+{
+    fn as_ref(&self) -> &[D::Color] {
+        flatten(flatten(&self.pixels))
+    }
+}
+
+impl<D, const X: usize, const Y: usize, const Z: usize, L> AsMut<[D::Color]>
+    for CubeBuffer<D, X, Y, Z, L>
+where
+    D: SmartLedsWrite,
+{
+    fn as_mut(&mut self) -> &mut [D::Color] {
+        flatten_mut(flatten_mut(&mut self.pixels))
+    }
+}
+
+/// The rows of `rows` as one slice, like `<[[T; N]]>::as_flattened` of newer Rust versions.
+fn flatten<T, const N: usize>(rows: &[[T; N]]) -> &[T] {
+    // SAFETY: Arrays are stored without padding, so `rows` are `rows.len() * N` consecutive `T`
+    unsafe { core::slice::from_raw_parts(rows.as_ptr().cast(), rows.len() * N) }
+}
+
+/// The rows of `rows` as one mutable slice, see [flatten].
+fn flatten_mut<T, const N: usize>(rows: &mut [[T; N]]) -> &mut [T] {
+    // SAFETY: See flatten, the slice borrows `rows` mutably
+    unsafe { core::slice::from_raw_parts_mut(rows.as_mut_ptr().cast(), rows.len() * N) }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::led::{
+        layout::{CubeSerpentine, Serpentine},
+        TestDriver,
+    };
+    use alloc::vec::Vec;
+    use smart_leds_trait::RGB8;
+
+    #[derive(Default, LedMatrix)]
+    struct Wrapper(FrameBuffer<TestDriver, 3, 2, Serpentine>);
+
+    #[derive(LedMatrix)]
+    struct Named {
+        #[led_matrix]
+        leds: FrameBuffer<TestDriver, 2, 2>,
+        _name: &'static str,
+    }
+
+    #[test]
+    fn layout() {
+        let mut matrix = FrameBuffer::<TestDriver, 3, 2, Serpentine>::new();
+        assert_eq!(
+            <FrameBuffer<TestDriver, 3, 2, Serpentine> as LedMatrix>::AREA,
+            6
+        );
+        let red = RGB8::new(255, 0, 0);
+        matrix.set_2d(0, 1, &red);
+        assert_eq!(matrix.get_2d(0, 1), Some(&red));
+        // Out of bounds writes are ignored
+        matrix.set_2d(3, 0, &red);
+        let mut expected = [RGB8::default(); 6];
+        expected[5] = red;
+        assert_eq!(matrix.read_buf(), expected);
+
+        // A frame is a copy, changing it leaves the matrix as it is
+        let mut frame = matrix.frame();
+        assert_eq!(frame.as_ref(), expected);
+        frame.as_mut().fill(red);
+        assert_eq!(matrix.read_buf(), expected);
+
+        matrix.set_buf(frame.as_mut());
+        assert_eq!(matrix.read_buf(), [red; 6]);
+        matrix.set_buf(&mut [RGB8::default(); 2]);
+        assert_eq!(matrix.read_buf(), [red; 6]);
+    }
+
+    #[test]
+    fn cube() {
+        type Test = CubeBuffer<TestDriver, 2, 2, 3, CubeSerpentine<Progressive>>;
+        assert_eq!(<Test as LedMatrix>::LEDS, 12);
+        assert_eq!(<FrameBuffer<TestDriver, 2, 2> as LedMatrix>::LEDS, 4);
+
+        let mut cube = Test::new();
+        let green = RGB8::new(0, 255, 0);
+        cube.set_3d(1, 0, 1, &green);
+        cube.set_2d(1, 1, &green);
+        cube.set_3d(0, 0, 3, &green);
+        assert_eq!(cube.get_3d(1, 0, 1), Some(&green));
+        assert_eq!(cube.get_3d(1, 1, 0), Some(&green));
+        assert_eq!(cube.get_3d(0, 0, 3), None);
+        let lit: Vec<_> = (0..12).filter(|i| cube.read_buf()[*i] == green).collect();
+        assert_eq!(lit, [3, 6]);
+    }
+
+    #[test]
+    fn derive() {
+        assert_eq!(<Wrapper as LedMatrix>::X, 3);
+        assert_eq!(<Wrapper as LedMatrix>::AREA, 6);
+        let blue = RGB8::new(0, 0, 255);
+        let mut wrapper = Wrapper::default();
+        wrapper.set_2d(2, 1, &blue);
+        assert_eq!(wrapper.read_buf()[3], blue);
+        assert_eq!(wrapper.get_2d(2, 1), Some(&blue));
+
+        let mut named = Named {
+            leds: FrameBuffer::new(),
+            _name: "test",
+        };
+        named.set_buf(&mut [blue; 4]);
+        assert_eq!(named.leds.read_buf(), [blue; 4]);
+    }
+}
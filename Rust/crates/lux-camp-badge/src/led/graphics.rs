@@ -0,0 +1,139 @@
+//! Draw onto an [LedMatrix] with [embedded-graphics](https://docs.rs/embedded-graphics),
+//! e.g. primitives, text or images from within an [Animation](super::Animation):
+//!
+//! ```ignore
+//! fn update(&mut self, _tick: Duration, matrix: &mut C) {
+//!     let mut canvas = Canvas::new(matrix);
+//!     canvas.clear(Rgb888::BLACK).ok();
+//!     Circle::new(Point::new(0, 0), 5)
+//!         .into_styled(PrimitiveStyle::with_stroke(Rgb888::RED, 1))
+//!         .draw(&mut canvas)
+//!         .ok();
+//! }
+//! ```
+//!
+//! Only available with the `embedded-graphics-core` feature.
+use core::convert::Infallible;
+
+use embedded_graphics_core::{
+    draw_target::DrawTarget,
+    geometry::{OriginDimensions, Size},
+    pixelcolor::{Rgb888, RgbColor},
+    Pixel,
+};
+use smart_leds_trait::{SmartLedsWrite, RGB8};
+
+use super::LedMatrix;
+
+/// Adapter implementing [DrawTarget] for an [LedMatrix] with [RGB8] colors.
+///
+/// The origin is the pixel at `x` / `y` = `0`, pixels outside of the matrix are skipped.
+pub struct Canvas<'a, M> {
+    matrix: &'a mut M,
+    z: usize,
+}
+
+impl<'a, M, D> Canvas<'a, M>
+where
+    M: LedMatrix<Driver = D>,
+    D: SmartLedsWrite<Color = RGB8>,
+{
+    /// Draw onto the `matrix`, or the bottom layer of an LED cube.
+    pub fn new(matrix: &'a mut M) -> Self {
+        Self::layer(matrix, 0)
+    }
+
+    /// Draw onto the layer `z` of an LED cube.
+    pub fn layer(matrix: &'a mut M, z: usize) -> Self {
+        Self { matrix, z }
+    }
+}
+
+impl<M, D> OriginDimensions for Canvas<'_, M>
+where
+    M: LedMatrix<Driver = D>,
+    D: SmartLedsWrite<Color = RGB8>,
+{
+    fn size(&self) -> Size {
+        Size::new(<M as LedMatrix>::X as u32, <M as LedMatrix>::Y as u32)
+    }
+}
+
+impl<M, D> DrawTarget for Canvas<'_, M>
+where
+    M: LedMatrix<Driver = D>,
+    D: SmartLedsWrite<Color = RGB8>,
+{
+    type Color = Rgb888;
+    type Error = Infallible;
+
+    fn draw_iter<I>(&mut self, pixels: I) -> Result<(), Self::Error>
+    where
+        I: IntoIterator<Item = Pixel<Self::Color>>,
+    {
+        if self.z >= <M as LedMatrix>::Z.max(1) {
+            return Ok(());
+        }
+        for Pixel(point, color) in pixels {
+            let (Ok(x), Ok(y)) = (usize::try_from(point.x), usize::try_from(point.y)) else {
+                continue;
+            };
+            if x < <M as LedMatrix>::X && y < <M as LedMatrix>::Y {
+                let color = RGB8::new(color.r(), color.g(), color.b());
+                self.matrix.set_3d(x, y, self.z, &color);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::led::{
+        framebuffer::{CubeBuffer, FrameBuffer},
+        layout::Serpentine,
+        TestDriver,
+    };
+    use embedded_graphics_core::{geometry::Point, primitives::Rectangle, Drawable};
+
+    #[test]
+    fn draw() {
+        let mut matrix = FrameBuffer::<TestDriver, 3, 2, Serpentine>::new();
+        let mut canvas = Canvas::new(&mut matrix);
+        assert_eq!(canvas.size(), Size::new(3, 2));
+
+        canvas.clear(Rgb888::new(0, 0, 1)).unwrap();
+        for point in [(2, 1), (-1, 0), (3, 0), (0, 2)] {
+            Pixel(Point::new(point.0, point.1), Rgb888::RED)
+                .draw(&mut canvas)
+                .unwrap();
+        }
+        let area = Rectangle::new(Point::new(0, 0), Size::new(2, 1));
+        canvas.fill_solid(&area, Rgb888::GREEN).unwrap();
+
+        let red = RGB8::new(255, 0, 0);
+        assert_eq!(matrix.get_2d(2, 1), Some(&red));
+        assert_eq!(matrix.get_2d(1, 0), Some(&RGB8::new(0, 255, 0)));
+        assert_eq!(matrix.get_2d(2, 0), Some(&RGB8::new(0, 0, 1)));
+        let lit = matrix.read_buf().iter().filter(|pixel| **pixel == red);
+        assert_eq!(lit.count(), 1);
+    }
+
+    #[test]
+    fn layer() {
+        let mut cube = CubeBuffer::<TestDriver, 2, 2, 3>::new();
+        Canvas::layer(&mut cube, 2).clear(Rgb888::BLUE).unwrap();
+        Canvas::layer(&mut cube, 3).clear(Rgb888::RED).unwrap();
+        let blue = RGB8::new(0, 0, 255);
+        assert_eq!(cube.get_3d(1, 1, 2), Some(&blue));
+        let lit = cube.read_buf().iter().filter(|pixel| **pixel == blue);
+        assert_eq!(lit.count(), 4);
+        assert!(cube.read_buf().iter().all(|pixel| pixel.r == 0));
+
+        // 2D matrices only have the bottom layer
+        let mut matrix = FrameBuffer::<TestDriver, 2, 2>::new();
+        Canvas::layer(&mut matrix, 1).clear(Rgb888::RED).unwrap();
+        assert_eq!(matrix.read_buf(), [RGB8::default(); 4]);
+    }
+}
@@ -0,0 +1,264 @@
+//! Common wirings of LED matrices, to be used in [LedMatrix::index_2d](super::LedMatrix::index_2d).
+//!
+//! A [Layout] maps the `x` / `y` coordinate of a pixel to the index of its LED on the strip,
+//! where `x` goes from left to right and `y` from top to bottom, starting at the top left.
+//! The wirings assume the first LED at the top left of the panel. Panels mounted in another
+//! orientation are handled by wrapping the wiring into a rotation or flip:
+//!
+//! ```ignore
+//! // Rows wired in a zigzag, with the panel mounted upside down
+//! type Wiring = Rotate180<Serpentine>;
+//!
+//! fn set_2d(&mut self, x: usize, y: usize, color: &Color<Self>) {
+//!     self.0[Self::index_2d(x, y)] = *color;
+//! }
+//!
+//! fn index_2d(x: usize, y: usize) -> usize {
+//!     Wiring::index(x, y, <Self as LedMatrix>::X, <Self as LedMatrix>::Y)
+//! }
+//! ```
+use core::marker::PhantomData;
+
+/// Maps coordinates to the index of the LED on the strip.
+pub trait Layout {
+    /// Index of the LED at `x` / `y` on a matrix of `width` x `height` pixels.
+    fn index(x: usize, y: usize, width: usize, height: usize) -> usize;
+}
+
+/// Row by row, each row from left to right.
+pub struct Progressive;
+
+impl Layout for Progressive {
+    fn index(x: usize, y: usize, width: usize, _height: usize) -> usize {
+        y * width + x
+    }
+}
+
+/// Row by row in a zigzag: even rows from left to right, odd rows from right to left.
+pub struct Serpentine;
+
+impl Layout for Serpentine {
+    fn index(x: usize, y: usize, width: usize, _height: usize) -> usize {
+        let x = if y % 2 == 1 { width - 1 - x } else { x };
+        y * width + x
+    }
+}
+
+/// Column by column, each column from top to bottom.
+pub struct ColumnFirst;
+
+impl Layout for ColumnFirst {
+    fn index(x: usize, y: usize, _width: usize, height: usize) -> usize {
+        x * height + y
+    }
+}
+
+/// Column by column in a zigzag: even columns from top to bottom, odd columns bottom up.
+pub struct ColumnSerpentine;
+
+impl Layout for ColumnSerpentine {
+    fn index(x: usize, y: usize, _width: usize, height: usize) -> usize {
+        let y = if x % 2 == 1 { height - 1 - y } else { y };
+        x * height + y
+    }
+}
+
+/// Maps coordinates of an LED cube to the index of the LED on the strip.
+///
+/// Like the wirings of 2D matrices, `z` counts the layers starting at the first LED.
+pub trait Layout3d {
+    /// Index of the LED at `x` / `y` / `z` on a cube of `width` x `height` x `depth` pixels.
+    fn index(x: usize, y: usize, z: usize, width: usize, height: usize, depth: usize) -> usize;
+}
+
+/// Layer by layer, each layer wired like `L`.
+pub struct Cube<L>(PhantomData<L>);
+
+impl<L: Layout> Layout3d for Cube<L> {
+    fn index(x: usize, y: usize, z: usize, width: usize, height: usize, _depth: usize) -> usize {
+        z * width * height + L::index(x, y, width, height)
+    }
+}
+
+/// Layer by layer in a zigzag: even layers are wired like `L`,
+/// odd layers in the reverse order, so the strip continues where the last layer ended.
+pub struct CubeSerpentine<L>(PhantomData<L>);
+
+impl<L: Layout> Layout3d for CubeSerpentine<L> {
+    fn index(x: usize, y: usize, z: usize, width: usize, height: usize, _depth: usize) -> usize {
+        let area = width * height;
+        let index = L::index(x, y, width, height);
+        z * area + if z % 2 == 1 { area - 1 - index } else { index }
+    }
+}
+
+/// The panel wired like `L` is mounted rotated by 90° clockwise,
+/// i.e. its bottom left LED is at the top left.
+pub struct Rotate90<L>(PhantomData<L>);
+
+impl<L: Layout> Layout for Rotate90<L> {
+    fn index(x: usize, y: usize, width: usize, height: usize) -> usize {
+        L::index(y, width - 1 - x, height, width)
+    }
+}
+
+/// The panel wired like `L` is mounted upside down.
+pub struct Rotate180<L>(PhantomData<L>);
+
+impl<L: Layout> Layout for Rotate180<L> {
+    fn index(x: usize, y: usize, width: usize, height: usize) -> usize {
+        L::index(width - 1 - x, height - 1 - y, width, height)
+    }
+}
+
+/// The panel wired like `L` is mounted rotated by 270° clockwise,
+/// i.e. its top right LED is at the top left.
+pub struct Rotate270<L>(PhantomData<L>);
+
+impl<L: Layout> Layout for Rotate270<L> {
+    fn index(x: usize, y: usize, width: usize, height: usize) -> usize {
+        L::index(height - 1 - y, x, height, width)
+    }
+}
+
+/// The panel wired like `L` is mirrored horizontally, i.e. its columns are right to left.
+pub struct FlipX<L>(PhantomData<L>);
+
+impl<L: Layout> Layout for FlipX<L> {
+    fn index(x: usize, y: usize, width: usize, height: usize) -> usize {
+        L::index(width - 1 - x, y, width, height)
+    }
+}
+
+/// The panel wired like `L` is mirrored vertically, i.e. its rows are bottom up.
+pub struct FlipY<L>(PhantomData<L>);
+
+impl<L: Layout> Layout for FlipY<L> {
+    fn index(x: usize, y: usize, width: usize, height: usize) -> usize {
+        L::index(x, height - 1 - y, width, height)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec::Vec;
+
+    /// The index of every pixel of a `width` x `height` matrix, row by row.
+    fn table<L: Layout>(width: usize, height: usize) -> Vec<usize> {
+        (0..height)
+            .flat_map(|y| (0..width).map(move |x| L::index(x, y, width, height)))
+            .collect()
+    }
+
+    /// The index of every pixel of a cube, layer by layer.
+    fn table_3d<L: Layout3d>(width: usize, height: usize, depth: usize) -> Vec<usize> {
+        (0..depth)
+            .flat_map(|z| {
+                (0..height).flat_map(move |y| {
+                    (0..width).map(move |x| L::index(x, y, z, width, height, depth))
+                })
+            })
+            .collect()
+    }
+
+    /// Every layout must map each pixel to its own LED.
+    fn bijective<L: Layout>() {
+        for width in 1..=6 {
+            for height in 1..=6 {
+                let mut indices = table::<L>(width, height);
+                indices.sort_unstable();
+                assert_eq!(indices, (0..width * height).collect::<Vec<_>>());
+            }
+        }
+    }
+
+    #[test]
+    fn wirings() {
+        // 3x2, as seen from the front:
+        // Progressive  Serpentine  ColumnFirst  ColumnSerpentine
+        // 0 1 2        0 1 2       0 2 4        0 3 4
+        // 3 4 5        5 4 3       1 3 5        1 2 5
+        assert_eq!(table::<Progressive>(3, 2), [0, 1, 2, 3, 4, 5]);
+        assert_eq!(table::<Serpentine>(3, 2), [0, 1, 2, 5, 4, 3]);
+        assert_eq!(table::<ColumnFirst>(3, 2), [0, 2, 4, 1, 3, 5]);
+        assert_eq!(table::<ColumnSerpentine>(3, 2), [0, 3, 4, 1, 2, 5]);
+    }
+
+    #[test]
+    fn transforms() {
+        // A 2x3 progressive panel (0 1 / 2 3 / 4 5), mounted as a 3x2 matrix
+        assert_eq!(table::<Rotate90<Progressive>>(3, 2), [4, 2, 0, 5, 3, 1]);
+        assert_eq!(table::<Rotate270<Progressive>>(3, 2), [1, 3, 5, 0, 2, 4]);
+        // A 3x2 progressive panel (0 1 2 / 3 4 5)
+        assert_eq!(table::<Rotate180<Progressive>>(3, 2), [5, 4, 3, 2, 1, 0]);
+        assert_eq!(table::<FlipX<Progressive>>(3, 2), [2, 1, 0, 5, 4, 3]);
+        assert_eq!(table::<FlipY<Progressive>>(3, 2), [3, 4, 5, 0, 1, 2]);
+
+        // Transforms compose with any wiring
+        assert_eq!(table::<Rotate180<Serpentine>>(3, 2), [3, 4, 5, 2, 1, 0]);
+        assert_eq!(table::<FlipX<ColumnFirst>>(3, 2), [4, 2, 0, 5, 3, 1]);
+        assert_eq!(table::<Rotate90<ColumnFirst>>(2, 2), [1, 0, 3, 2]);
+    }
+
+    #[test]
+    fn inverse_transforms() {
+        for (width, height) in [(1, 1), (2, 3), (4, 4), (5, 2)] {
+            let progressive = table::<Progressive>(width, height);
+            assert_eq!(
+                table::<Rotate90<Rotate270<Progressive>>>(width, height),
+                progressive
+            );
+            assert_eq!(
+                table::<Rotate180<Rotate180<Progressive>>>(width, height),
+                progressive
+            );
+            assert_eq!(
+                table::<FlipX<FlipY<Progressive>>>(width, height),
+                table::<Rotate180<Progressive>>(width, height)
+            );
+            assert_eq!(
+                table::<Rotate90<Rotate90<Progressive>>>(width, height),
+                table::<Rotate180<Progressive>>(width, height)
+            );
+        }
+    }
+
+    #[test]
+    fn cubes() {
+        // 2x2x3, layer by layer
+        assert_eq!(
+            table_3d::<Cube<Progressive>>(2, 2, 3),
+            [0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11]
+        );
+        assert_eq!(
+            table_3d::<Cube<Serpentine>>(2, 2, 3),
+            [0, 1, 3, 2, 4, 5, 7, 6, 8, 9, 11, 10]
+        );
+        assert_eq!(
+            table_3d::<CubeSerpentine<Progressive>>(2, 2, 3),
+            [0, 1, 2, 3, 7, 6, 5, 4, 8, 9, 10, 11]
+        );
+
+        for (width, height, depth) in [(1, 1, 1), (2, 3, 4), (4, 4, 4), (5, 1, 2)] {
+            let mut indices =
+                table_3d::<CubeSerpentine<Rotate90<Serpentine>>>(width, height, depth);
+            indices.sort_unstable();
+            assert_eq!(indices, (0..width * height * depth).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn bijections() {
+        bijective::<Progressive>();
+        bijective::<Serpentine>();
+        bijective::<ColumnFirst>();
+        bijective::<ColumnSerpentine>();
+        bijective::<Rotate90<Serpentine>>();
+        bijective::<Rotate180<ColumnSerpentine>>();
+        bijective::<Rotate270<ColumnFirst>>();
+        bijective::<FlipX<Serpentine>>();
+        bijective::<FlipY<ColumnSerpentine>>();
+        bijective::<Rotate90<FlipX<Serpentine>>>();
+    }
+}
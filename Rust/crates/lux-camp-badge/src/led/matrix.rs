@@ -11,18 +11,19 @@
 //! To re-use any of our animations you only need to:
 //! * Find (or implement) a suitable [SmartLedsWrite] driver;
 //!   a popular choice working with most neopixel-like LEDs is `Ws2812Esp32Rmt`.
-//! * Implement the `LedMatrix` trait according to the physical properties of your matrix.
+//! * Implement the `LedMatrix` trait according to the physical properties of your matrix,
+//!   or use a [FrameBuffer](super::framebuffer::FrameBuffer) with a suitable layout.
 //!
 //! ```
-//! use lux_camp_badge::led::{Animation, Color, LedMatrix};
+//! use lux_camp_badge::led::{layout::{ColumnFirst, Layout}, Animation, Color, LedMatrix};
 //! use lux_camp_badge_animations::prelude::*;
 //! use ws2812_esp32_rmt_driver::Ws2812Esp32Rmt;
 //!
 //! // Matrix backend holding the frame buffer
 //! #[derive(Default)]
 //! struct MyMatrix(
-//!     // Match the phyisical matrix properties
-//!     [Color<Self>; <Self as LedMatrix>::AREA],
+//!     // Match the phyisical matrix properties, one color per LED
+//!     [Color<Self>; 25],
 //! );
 //!
 //! // Any 5x5 matrix where the Ws2812Esp32Rmt driver just works:
@@ -30,6 +31,11 @@
 //!     const X: usize = 5;
 //!     const Y: usize = 5;
 //!     type Driver = Ws2812Esp32Rmt;
+//!     type Frame = [Color<Self>; 25];
+//!
+//!     fn frame(&self) -> Self::Frame {
+//!         self.0
+//!     }
 //!
 //!     fn read_buf(&self) -> &[Color<Self>] {
 //!         &self.0
@@ -42,9 +48,13 @@
 //!         }
 //!     }
 //!
-//!     fn set_2d(&mut self, x: usize, y: usize, color: Color<Self>) {
-//!         // This highly depends on how your matrix is wired up
-//!         self.0[x * <Self as LedMatrix>::X + y] = color;
+//!     fn set_2d(&mut self, x: usize, y: usize, color: &Color<Self>) {
+//!         self.0[Self::index_2d(x, y)] = *color;
+//!     }
+//!
+//!     fn index_2d(x: usize, y: usize) -> usize {
+//!         // This highly depends on how your matrix is wired up, see the layout module
+//!         ColumnFirst::index(x, y, <Self as LedMatrix>::X, <Self as LedMatrix>::Y)
 //!     }
 //! }
 //!
@@ -58,27 +68,50 @@
 //! // Change the animation to a shiny rainbow:
 //! matrix::update(&handle, rainbow::Sliding::build(5, None))?;
 //! ```
-use esp_idf_svc::systime::EspSystemTime;
-use lazy_static::lazy_static;
+use alloc::{
+    boxed::Box,
+    format,
+    string::{String, ToString},
+    vec::Vec,
+};
+#[cfg(not(feature = "std"))]
+use core::cell::RefCell;
+use core::{cell::Cell, fmt::Debug, marker::PhantomData, ops::DerefMut, time::Duration};
 use smart_leds_trait::SmartLedsWrite;
+#[cfg(feature = "std")]
+use smart_leds_trait::RGB8;
+#[cfg(feature = "std")]
 use std::{
-    fmt::Debug,
-    marker::PhantomData,
-    sync::{Arc, Mutex},
+    convert::Infallible,
+    fmt::Write as _,
+    io::{Stdout, Write},
+    panic::{self, AssertUnwindSafe},
+    sync::{
+        mpsc::{self, Receiver, RecvTimeoutError, Sender},
+        Arc, Mutex,
+    },
     thread::JoinHandle,
-    time::Duration,
 };
 
 pub use self::state::AnimationSet;
-use super::{Animation, Dimmable, LedMatrix};
-
-lazy_static! {
-    static ref STOP: Arc<Mutex<bool>> = Arc::new(Mutex::new(false));
-}
+use super::{
+    clock::Clock,
+    correction::{Correctable, Correction},
+    power::{PowerDraw, PowerModel},
+    stats::{Stats, Window},
+    supervisor::{Idle, Incident, Supervisor},
+    transition::Transition,
+    Animation, Blend, Dimmable, LedMatrix,
+};
+#[cfg(feature = "std")]
+use super::{
+    clock::MockClock,
+    record::{Frame, Recorder, Recording},
+};
 
 #[derive(Debug)]
 pub enum Error<T: Debug> {
-    /// A thread holding a mutex to an instance or `STOP` flag did panic.
+    /// A thread holding a mutex to an instance did panic.
     Poisoned,
     /// The thread running the matrix animations did panic.
     Paniced,
@@ -107,6 +140,78 @@ impl SmartLedsWrite for DummyBackend {
     }
 }
 
+/// Backend rendering each frame as ANSI true-color blocks in a terminal.
+///
+/// Useful to develop animations on the host without flashing a badge.
+/// Pixels are printed in the order they are written, `width` pixels per row.
+/// Consecutive frames overwrite each other in place.
+#[cfg(feature = "std")]
+pub struct TerminalBackend<W: Write = Stdout> {
+    out: W,
+    width: usize,
+    rows: usize,
+}
+
+#[cfg(feature = "std")]
+impl TerminalBackend {
+    /// Render frames with `width` pixels per row to `stdout`.
+    pub fn new(width: usize) -> Self {
+        Self::with_writer(std::io::stdout(), width)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> TerminalBackend<W> {
+    /// Render frames with `width` pixels per row to any writer.
+    pub fn with_writer(out: W, width: usize) -> Self {
+        Self {
+            out,
+            width: width.max(1),
+            rows: 0,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<W: Write> SmartLedsWrite for TerminalBackend<W> {
+    type Error = std::io::Error;
+    type Color = RGB8;
+
+    fn write<T, I>(&mut self, iterator: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = I>,
+        I: Into<Self::Color>,
+    {
+        let mut frame = String::new();
+        // Move the cursor back up to draw over the previous frame
+        if self.rows > 0 {
+            let _ = write!(frame, "\x1b[{}A", self.rows);
+        }
+
+        let mut rows = 0;
+        let mut column = 0;
+        for pixel in iterator {
+            let RGB8 { r, g, b } = pixel.into();
+            // Two characters per LED, so pixels appear roughly square
+            let _ = write!(frame, "\x1b[38;2;{r};{g};{b}m\u{2588}\u{2588}");
+            column += 1;
+            if column == self.width {
+                frame.push_str("\x1b[0m\n");
+                column = 0;
+                rows += 1;
+            }
+        }
+        if column > 0 {
+            frame.push_str("\x1b[0m\n");
+            rows += 1;
+        }
+
+        self.rows = rows;
+        self.out.write_all(frame.as_bytes())?;
+        self.out.flush()
+    }
+}
+
 /// A missing state in the builder means that a mandotory field was not yet set.
 pub struct Missing<State>(PhantomData<fn() -> State>);
 #[derive(Clone)]
@@ -118,6 +223,11 @@ impl LedMatrix for DummyMatrix {
     const Y: usize = 0;
 
     type Driver = DummyBackend;
+    type Frame = [<Self::Driver as SmartLedsWrite>::Color; 0];
+
+    fn frame(&self) -> Self::Frame {
+        unimplemented!()
+    }
 
     fn read_buf(&self) -> &[<Self::Driver as SmartLedsWrite>::Color] {
         unimplemented!()
@@ -130,6 +240,10 @@ impl LedMatrix for DummyMatrix {
     fn set_2d(&mut self, _x: usize, _y: usize, _color: &<Self::Driver as SmartLedsWrite>::Color) {
         unimplemented!()
     }
+
+    fn index_2d(_x: usize, _y: usize) -> usize {
+        unimplemented!()
+    }
 }
 
 impl crate::led::Animation<DummyMatrix> for () {}
@@ -141,18 +255,65 @@ impl crate::led::Animation<DummyMatrix> for () {}
 #[must_use]
 pub struct MatrixBuilder<S: LedMatrix, AnimationState> {
     animation: Option<Box<dyn Animation<S> + Send>>,
-    fps: u8,
+    clock: Option<Box<dyn Clock>>,
+    correction: Option<Correction>,
+    fps: f32,
     matrix: S,
+    power: Option<PowerModel>,
+    supervisor: Option<Supervisor<S>>,
+    transition: Option<(Transition, fn(&S) -> S)>,
     marker: PhantomData<fn() -> AnimationState>,
 }
 
 impl<S: LedMatrix, A> MatrixBuilder<S, A> {
     /// Set the `frames per seconds` (FPS) rate of the animations.
     /// In other words, define the refresh rate of the LED matrix.
-    pub fn fps(mut self, n: u8) -> Self {
+    ///
+    /// Fractional rates are fine, e.g. `0.2` for a frame every five seconds.
+    /// The rate can be changed at runtime via the [fps] function of this module.
+    pub fn fps(mut self, n: f32) -> Self {
         self.fps = n;
         self
     }
+
+    /// Set the time source used to schedule frames.
+    pub fn clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Some(Box::new(clock));
+        self
+    }
+
+    /// Apply gamma and color correction to every pixel written to the LEDs.
+    /// The frame buffer of the matrix remains unchanged.
+    pub fn correction(mut self, correction: Correction) -> Self {
+        self.correction = Some(correction);
+        self
+    }
+
+    /// Limit the current drawn by the LEDs. Frames exceeding the budget are dimmed.
+    /// The estimated current of the last frame can be read via [power_draw].
+    pub fn power(mut self, model: PowerModel) -> Self {
+        self.power = Some(model);
+        self
+    }
+
+    /// Recover from driver errors and panicking animations according to `supervisor`,
+    /// instead of stopping the matrix. Panics are only recovered from on targets which
+    /// unwind, the ESP aborts on panics.
+    pub fn supervise(mut self, supervisor: Supervisor<S>) -> Self {
+        self.supervisor = Some(supervisor);
+        self
+    }
+}
+
+impl<S: LedMatrix + Clone, A> MatrixBuilder<S, A> {
+    /// Blend from one animation to the next when changing animations at runtime,
+    /// instead of cutting over immediately.
+    ///
+    /// During the transition both animations are running, each in its own copy of the matrix.
+    pub fn transition(mut self, transition: Transition) -> Self {
+        self.transition = Some((transition, S::clone));
+        self
+    }
 }
 
 impl<S: LedMatrix> MatrixBuilder<S, Missing<AnimationSet>> {
@@ -165,8 +326,13 @@ impl<S: LedMatrix> MatrixBuilder<S, Missing<AnimationSet>> {
     ) -> MatrixBuilder<S, AnimationSet> {
         MatrixBuilder {
             animation: Some(animation),
+            clock: self.clock,
+            correction: self.correction,
             fps: self.fps,
             matrix: self.matrix,
+            power: self.power,
+            supervisor: self.supervisor,
+            transition: self.transition,
             marker: PhantomData,
         }
     }
@@ -177,32 +343,116 @@ where
     S: LedMatrix<Driver = B> + Send + 'static,
     B: SmartLedsWrite + Send + 'static,
     B::Error: Send + Debug,
-    <B as SmartLedsWrite>::Color: Clone + Dimmable,
+    <B as SmartLedsWrite>::Color: Clone + Dimmable + Blend + Correctable + PowerDraw,
 {
     /// Start the matrix in a background thread.
+    #[cfg(feature = "std")]
     pub fn run(
-        mut self,
+        self,
         driver: <S as LedMatrix>::Driver,
     ) -> Result<Arc<Mutex<Option<Handle<S, B>>>>, Error<<B as SmartLedsWrite>::Error>> {
+        let matrix = self.build(driver)?;
+        Ok(Arc::new(Mutex::new(Some(matrix.run()))))
+    }
+
+    /// Create the matrix without starting it, to drive it via [Matrix::tick] from your own loop,
+    /// e.g. on targets without threads. The first frame is drawn right away.
+    pub fn build(
+        mut self,
+        driver: <S as LedMatrix>::Driver,
+    ) -> Result<Matrix<S, B>, Error<<B as SmartLedsWrite>::Error>> {
+        let now = self
+            .clock
+            .as_ref()
+            .map_or(Duration::ZERO, |clock| clock.now());
         let mut matrix = Matrix {
             animation: self.animation.take().unwrap(),
             backend: self.matrix,
-            brightness: None,
+            brightness: u8::MAX,
+            clock: self.clock,
+            correction: self.correction,
             driver,
-            cycle_time: Duration::from_millis(1000 / self.fps as u64),
+            cycle_time: cycle_time(self.fps),
+            fading: None,
+            frame: now,
             frame_rate: None,
-            tick: EspSystemTime {}.now(),
+            fps: self.fps,
+            epoch: (now, now),
+            paused: None,
+            power: self.power,
+            failures: 0,
+            retry: None,
+            supervisor: self.supervisor,
+            telemetry: Default::default(),
+            tick: now,
+            transition: self.transition,
+            window: Window::new(now),
         };
         matrix.init_animation()?;
-        Ok(Arc::new(Mutex::new(Some(Handle(matrix.run())))))
+        matrix.publish()?;
+        Ok(matrix)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<S> MatrixBuilder<S, AnimationSet>
+where
+    S: LedMatrix<Driver = Recorder> + Clone + Send + 'static,
+{
+    /// Run the matrix for `frames` frames on a simulated clock and record the output,
+    /// see [record](super::record). This returns as soon as all frames are computed.
+    /// Of LED cubes, only the bottom layer is recorded.
+    pub fn record(self, frames: usize) -> Result<Recording, Error<Infallible>> {
+        // The index of each pixel in the frame buffer, row by row
+        let mut layout = Vec::with_capacity(<S as LedMatrix>::AREA);
+        for y in 0..<S as LedMatrix>::Y {
+            for x in 0..<S as LedMatrix>::X {
+                layout.push(match <S as LedMatrix>::Z {
+                    0 => S::index_2d(x, y),
+                    _ => S::index_3d(x, y, 0),
+                });
+            }
+        }
+
+        let clock = MockClock::default();
+        let step = cycle_time(self.fps);
+        let mut matrix = self
+            .clock(clock.clone())
+            .build(Recorder::new(clock.clone()))?;
+        for _ in 1..frames {
+            clock.advance(step);
+            matrix.tick(clock.now())?;
+        }
+
+        let frames = matrix
+            .driver
+            .into_frames()
+            .into_iter()
+            .map(|frame| Frame {
+                time: frame.time,
+                pixels: layout
+                    .iter()
+                    .map(|i| frame.pixels.get(*i).copied().unwrap_or_default())
+                    .collect(),
+            })
+            .collect();
+        Ok(Recording {
+            width: <S as LedMatrix>::X,
+            height: <S as LedMatrix>::Y,
+            frames,
+            length: clock.now().saturating_add(step),
+        })
     }
 }
 
 /// A generic LED matrix implementation.
 ///
 /// Create an instance via the [MatrixBuilder] and change animations
-/// at runtime via the [update] function using the [Handle] from the builder.
+/// at runtime via the [update] (or [send]) function using the [Handle] from the builder.
 /// This guarantees correct and thread-safe usage.
+///
+/// Without threads, [MatrixBuilder::build] returns the matrix itself instead.
+/// It is driven by calling [Matrix::tick] and controlled via [Matrix::apply].
 pub struct Matrix<S, B>
 where
     S: LedMatrix<Driver = B>,
@@ -211,11 +461,54 @@ where
 {
     animation: Box<dyn Animation<S> + Send>,
     backend: S,
-    brightness: Option<u8>,
+    brightness: u8,
+    clock: Option<Box<dyn Clock>>,
+    correction: Option<Correction>,
     driver: S::Driver,
     cycle_time: Duration,
+    fading: Option<Fading<S>>,
+    /// Start of the current frame.
+    frame: Duration,
     frame_rate: Option<Duration>,
+    fps: f32,
+    /// Time of the clock and of the animations when the matrix was last (re)started.
+    /// The time of the animations doesn't move while the matrix is paused.
+    epoch: (Duration, Duration),
+    /// The frozen time of the animations while paused.
+    paused: Option<Duration>,
+    power: Option<PowerModel>,
+    /// Failed writes in a row, reset by the next successful write.
+    failures: u32,
+    /// When to retry writing after the last failed write.
+    retry: Option<Duration>,
+    supervisor: Option<Supervisor<S>>,
+    telemetry: Shared<Telemetry>,
+    /// Time of the last animation update, in the time of the animations.
     tick: Duration,
+    /// The kind of transition and how to copy the matrix for it.
+    transition: Option<(Transition, fn(&S) -> S)>,
+    /// Frame timing measured since the last [Stats] were published.
+    window: Window,
+}
+
+/// Telemetry shared with the [Handle] of a threaded matrix.
+#[cfg(feature = "std")]
+type Shared<T> = Arc<Mutex<T>>;
+/// Without threads, the telemetry is only read from the matrix itself.
+#[cfg(not(feature = "std"))]
+type Shared<T> = RefCell<T>;
+
+/// State of a running transition from the previous to the current animation.
+struct Fading<S: LedMatrix> {
+    kind: Transition,
+    start: Duration,
+    /// The previous animation, still running in its own copy of the matrix.
+    from: Box<dyn Animation<S> + Send>,
+    from_matrix: S,
+    from_frame_rate: Option<Duration>,
+    from_tick: Duration,
+    /// Copy of the matrix the current animation renders into until the transition is done.
+    to_matrix: S,
 }
 
 impl<S, B> Matrix<S, B>
@@ -223,154 +516,1348 @@ where
     S: LedMatrix<Driver = B> + Send + 'static,
     B: SmartLedsWrite + Send + 'static,
     B::Error: Send + Debug,
-    <B as SmartLedsWrite>::Color: Clone + Dimmable,
+    <B as SmartLedsWrite>::Color: Clone + Dimmable + Blend + Correctable + PowerDraw,
 {
     /// Creates a new [MatrixBuilder] with the following defaults:
     /// * `fps`: 24
+    /// * `clock`: The system time of the ESP (or [StdClock](super::clock::StdClock) on other targets).
+    ///   Without `std`, the time passed to [Matrix::tick] is used.
     #[allow(clippy::new_ret_no_self)]
     pub fn new(matrix: S) -> MatrixBuilder<S, Missing<AnimationSet>> {
         MatrixBuilder {
             animation: None,
-            fps: 24,
+            clock: super::clock::default(),
+            correction: None,
+            fps: 24.0,
             matrix,
+            power: None,
+            supervisor: None,
+            transition: None,
             marker: PhantomData,
         }
     }
 
     fn init_animation(&mut self) -> Result<(), Error<<B as SmartLedsWrite>::Error>> {
-        self.frame_rate = self.animation.init(&mut self.backend);
+        let (animation, backend) = (&mut self.animation, &mut self.backend);
+        match guard(self.supervisor.is_some(), || animation.init(backend)) {
+            Ok(frame_rate) => self.frame_rate = frame_rate,
+            Err(message) => self.recover(message),
+        }
         self.draw_framebuffer()
     }
 
+    /// Replace the current animation, which panicked with `message`,
+    /// by the fallback of the [Supervisor]. A running transition is cut short.
+    fn recover(&mut self, message: String) {
+        let Some(supervisor) = &mut self.supervisor else {
+            return;
+        };
+        supervisor.notify(Incident::Panic {
+            animation: self.animation.name().to_string(),
+            message,
+        });
+        self.fading = None;
+        self.animation = supervisor.fallback_animation();
+        let (animation, backend) = (&mut self.animation, &mut self.backend);
+        self.frame_rate = match guard(true, || animation.init(backend)) {
+            Ok(frame_rate) => frame_rate,
+            Err(message) => {
+                supervisor.notify(Incident::Panic {
+                    animation: self.animation.name().to_string(),
+                    message,
+                });
+                self.animation = Box::new(Idle);
+                None
+            }
+        };
+    }
+
     fn set_animation(
         &mut self,
         animation: Box<dyn Animation<S> + Send>,
     ) -> Result<(), Error<<B as SmartLedsWrite>::Error>> {
-        self.animation = animation;
-        self.init_animation()
+        let Some((kind, copy)) = self.transition else {
+            self.fading = None;
+            self.animation = animation;
+            return self.init_animation();
+        };
+
+        // An unfinished transition is cut short, continuing from its target
+        let from_matrix = match self.fading.take() {
+            Some(fading) => fading.to_matrix,
+            None => copy(&self.backend),
+        };
+        let mut to_matrix = copy(&from_matrix);
+        let from = core::mem::replace(&mut self.animation, animation);
+        let from_frame_rate = self.frame_rate;
+        let animation = &mut self.animation;
+        match guard(self.supervisor.is_some(), || animation.init(&mut to_matrix)) {
+            Ok(frame_rate) => self.frame_rate = frame_rate,
+            Err(message) => {
+                self.recover(message);
+                return self.draw_framebuffer();
+            }
+        }
+
+        let now = self.animation_time(self.now());
+        self.fading = Some(Fading {
+            kind,
+            start: now,
+            from,
+            from_matrix,
+            from_frame_rate,
+            from_tick: self.tick,
+            to_matrix,
+        });
+        self.tick = now;
+        self.blend(now);
+        self.draw_framebuffer()
+    }
+
+    /// Mix the frames of a running transition into the frame buffer.
+    /// Completes the transition once its duration has elapsed.
+    fn blend(&mut self, now: Duration) {
+        let Some(fading) = &self.fading else {
+            return;
+        };
+
+        let progress = fading.kind.progress(now.saturating_sub(fading.start));
+        if progress == u8::MAX {
+            if let Some(fading) = self.fading.take() {
+                self.backend = fading.to_matrix;
+            }
+            return;
+        }
+
+        // Each amount goes to the index of its pixel, so it follows the wiring of the matrix.
+        // Cubes get the same amounts on every layer.
+        let (from, to) = (fading.from_matrix.read_buf(), fading.to_matrix.read_buf());
+        let mut buf = self.backend.frame();
+        let pixels = buf.as_mut();
+        for y in 0..<S as LedMatrix>::Y {
+            for x in 0..<S as LedMatrix>::X {
+                let amount = fading.kind.amount(x, y, <S as LedMatrix>::X, progress);
+                for z in 0..<S as LedMatrix>::Z.max(1) {
+                    let index = match <S as LedMatrix>::Z {
+                        0 => S::index_2d(x, y),
+                        _ => S::index_3d(x, y, z),
+                    };
+                    if let (Some(pixel), Some(from), Some(to)) =
+                        (pixels.get_mut(index), from.get(index), to.get(index))
+                    {
+                        *pixel = from.blend(to, amount);
+                    }
+                }
+            }
+        }
+        self.backend.set_buf(buf.as_mut());
     }
 
     // Draws the current frame buffer. This is allocation-free (assuming Color::clone()
     // is allocation-free). This is not zero-copy, because the SmartLedsWrite
     // requires an iterator yielding color values instead of a slice of colors.
+    //
+    // Each pixel passes the output pipeline: gamma and color correction, then brightness.
+    // If the resulting frame exceeds the power budget, it is dimmed as a whole.
     fn draw_framebuffer(
         &mut self,
     ) -> Result<(), crate::led::matrix::Error<<B as SmartLedsWrite>::Error>> {
-        let pixels = self.backend.read_buf().into_iter().cloned();
-        match self.brightness {
-            Some(level) => self.driver.write(pixels.map(|mut pixel| {
-                pixel.dimm(level);
-                pixel
-            })),
-            _ => self.driver.write(pixels.into_iter()),
-        }
-        .map_err(Error::Driver)
-    }
-
-    fn run(mut self) -> JoinHandle<Result<Matrix<S, B>, Error<<B as SmartLedsWrite>::Error>>> {
-        std::thread::spawn(|| loop {
-            std::thread::sleep(
-                self.cycle_time
-                    .saturating_sub(EspSystemTime {}.now() - self.tick),
-            );
-            if *STOP.lock().map_err(|_| Error::Poisoned)? {
-                return Ok(self);
+        // Wait for the backoff after a failed write, the next frame is written later on
+        if self.retry.is_some_and(|at| self.now() < at) {
+            return Ok(());
+        }
+        let correction = self.correction.as_ref();
+        let brightness = self.brightness;
+        let output = |pixel: &<B as SmartLedsWrite>::Color| {
+            let mut pixel = pixel.clone();
+            if let Some(correction) = correction {
+                pixel.correct(correction);
+            }
+            if brightness < u8::MAX {
+                pixel.dimm(brightness);
             }
+            pixel
+        };
 
-            let now = EspSystemTime {}.now();
-            if self
-                .frame_rate
-                .is_some_and(|step| self.tick.saturating_add(step) > now)
-            {
-                continue;
+        let buf = self.backend.read_buf();
+        let limit = self.power.and_then(|model| {
+            let current = buf.iter().map(|pixel| output(pixel).current(&model)).sum();
+            model.limit(buf.len(), current)
+        });
+        let drawn = Cell::new(0);
+        let pixels = buf.iter().map(|pixel| {
+            let mut pixel = output(pixel);
+            if let Some(scale) = limit {
+                pixel.reduce(scale);
+            }
+            if let Some(model) = &self.power {
+                drawn.set(drawn.get() + pixel.current(model));
             }
+            pixel
+        });
+        let start = self.now();
+        let written = self.driver.write(pixels);
+        self.window.write(self.now().saturating_sub(start));
+        if let Err(error) = written {
+            return self.failed(error);
+        }
+        if self.failures > 0 {
+            self.telemetry()?.status.error = None;
+        }
+        self.failures = 0;
+        self.retry = None;
 
-            self.tick = now;
-            self.animation.update(now, &mut self.backend);
-            self.draw_framebuffer()?;
+        if self.power.is_some() {
+            self.telemetry()?.power_draw = Some(drawn.get() / 1000);
+        }
+        Ok(())
+    }
+
+    /// Handle a failed write. Unless supervised, the matrix stops with the `error`.
+    fn failed(
+        &mut self,
+        error: <B as SmartLedsWrite>::Error,
+    ) -> Result<(), Error<<B as SmartLedsWrite>::Error>> {
+        let Some(supervisor) = &mut self.supervisor else {
+            return Err(Error::Driver(error));
+        };
+        self.failures = self.failures.saturating_add(1);
+        if supervisor.gives_up(self.failures) {
+            supervisor.notify(Incident::GaveUp {
+                error: format!("{error:?}"),
+            });
+            return Err(Error::Driver(error));
+        }
+        let backoff = supervisor.backoff(self.failures);
+        let error = format!("{error:?}");
+        supervisor.notify(Incident::Driver {
+            error: error.clone(),
+            attempt: self.failures,
+            backoff,
+        });
+        self.telemetry()?.status.error = Some(error);
+        self.retry = Some(self.now().saturating_add(backoff));
+        Ok(())
+    }
+
+    /// Apply a [Command], e.g. received from the [Handle].
+    /// It takes effect right away, the next frame is drawn by the next [Matrix::tick].
+    pub fn apply(
+        &mut self,
+        command: Command<S>,
+    ) -> Result<(), Error<<B as SmartLedsWrite>::Error>> {
+        self.execute(command)?;
+        self.publish()
+    }
+
+    fn execute(&mut self, command: Command<S>) -> Result<(), Error<<B as SmartLedsWrite>::Error>> {
+        match command {
+            Command::Animation(animation) => self.set_animation(animation),
+            Command::Brightness(level) => {
+                self.brightness = level;
+                self.draw_framebuffer()
+            }
+            Command::Fps(n) => {
+                self.fps = n;
+                self.cycle_time = cycle_time(n);
+                Ok(())
+            }
+            Command::Pause(true) => {
+                let now = self.now();
+                self.paused = Some(self.animation_time(now));
+                Ok(())
+            }
+            Command::Pause(false) => {
+                // Continue where the animations were paused, instead of skipping the pause
+                if let Some(time) = self.paused.take() {
+                    self.epoch = (self.now(), time);
+                }
+                Ok(())
+            }
+            Command::Step => {
+                let now = self.now();
+                let time = self.animation_time(now).saturating_add(self.cycle_time);
+                self.paused = Some(time);
+                self.advance(time, true)
+            }
+        }
+    }
+
+    /// Share the current [Status] with the [Handle].
+    fn publish(&self) -> Result<(), Error<<B as SmartLedsWrite>::Error>> {
+        let mut telemetry = self.telemetry()?;
+        let error = telemetry.status.error.take();
+        telemetry.status = Status {
+            animation: self.animation.name().to_string(),
+            params: self.animation.params(),
+            brightness: self.brightness,
+            fps: self.fps,
+            paused: self.paused.is_some(),
+            running: true,
+            error,
+        };
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    fn telemetry(
+        &self,
+    ) -> Result<impl DerefMut<Target = Telemetry> + '_, Error<<B as SmartLedsWrite>::Error>> {
+        self.telemetry.lock().map_err(|_| Error::Poisoned)
+    }
+
+    #[cfg(not(feature = "std"))]
+    fn telemetry(
+        &self,
+    ) -> Result<impl DerefMut<Target = Telemetry> + '_, Error<<B as SmartLedsWrite>::Error>> {
+        Ok(self.telemetry.borrow_mut())
+    }
+
+    /// The current animation and settings of the matrix, see [status].
+    pub fn status(&self) -> Result<Status, Error<<B as SmartLedsWrite>::Error>> {
+        Ok(self.telemetry()?.status.clone())
+    }
+
+    /// Frame timing statistics of the matrix, see [stats].
+    pub fn stats(&self) -> Result<Option<Stats>, Error<<B as SmartLedsWrite>::Error>> {
+        Ok(self.telemetry()?.stats)
+    }
+
+    /// The estimated current in mA drawn by the last frame, see [power_draw].
+    pub fn power_draw(&self) -> Result<Option<u32>, Error<<B as SmartLedsWrite>::Error>> {
+        Ok(self.telemetry()?.power_draw)
+    }
+
+    /// When the next frame is due according to the `fps` rate, in the time of [Matrix::tick].
+    pub fn next_frame(&self) -> Duration {
+        self.frame.saturating_add(self.cycle_time)
+    }
+
+    /// The current time of the clock. Without a clock, the time stands still
+    /// at the start of the current frame.
+    fn now(&self) -> Duration {
+        clock_time(&self.clock, self.frame)
+    }
+
+    /// The time of the animations at the time `now` of the clock.
+    fn animation_time(&self, now: Duration) -> Duration {
+        self.paused.unwrap_or_else(|| {
+            self.epoch
+                .1
+                .saturating_add(now.saturating_sub(self.epoch.0))
         })
     }
+
+    #[cfg(feature = "std")]
+    fn run(self) -> Handle<S, B> {
+        let (control, receiver) = mpsc::channel();
+        let telemetry = self.telemetry.clone();
+        let thread = std::thread::spawn(move || {
+            let telemetry = self.telemetry.clone();
+            let result = self.run_loop(&receiver);
+            // Keep the reason the matrix stopped for the status
+            if let (Err(err), Ok(mut telemetry)) = (&result, telemetry.lock()) {
+                telemetry.status.error = Some(format!("{err:?}"));
+            }
+            result
+        });
+        Handle {
+            thread,
+            control,
+            telemetry,
+        }
+    }
+
+    #[cfg(feature = "std")]
+    fn run_loop(
+        mut self,
+        receiver: &Receiver<Control<S>>,
+    ) -> Result<Self, Error<<B as SmartLedsWrite>::Error>> {
+        loop {
+            let next = self.frame.saturating_add(self.cycle_time);
+            let wait = next.saturating_sub(self.now());
+            // Commands are applied between frames as soon as they arrive,
+            // so they take effect right away even at very low frame rates.
+            match receiver.recv_timeout(wait) {
+                Ok(Control::Command(command)) => self.apply(command)?,
+                Ok(Control::Stop) => return Ok(self),
+                Err(RecvTimeoutError::Timeout) => self.tick(self.now())?,
+                // The handle was dropped, the matrix keeps running with its current animation
+                Err(RecvTimeoutError::Disconnected) => {
+                    std::thread::sleep(wait);
+                    self.tick(self.now())?;
+                }
+            }
+        }
+    }
+
+    /// Start a new frame at the time `now`, e.g. of the clock of the matrix.
+    /// The animation is only updated (and drawn) if it is due according to its frame rate.
+    /// During a transition, the frame is redrawn every time.
+    ///
+    /// Call this once per frame, at [Matrix::next_frame], to run the matrix from your own loop.
+    pub fn tick(&mut self, now: Duration) -> Result<(), Error<<B as SmartLedsWrite>::Error>> {
+        self.window
+            .frame(now.saturating_sub(self.frame), self.cycle_time);
+        self.frame = now;
+        let result = self.render(now);
+
+        if let Some(stats) = self.window.finish(now) {
+            self.telemetry()?.stats = Some(stats);
+            // The parameters of the animation may change while it is running
+            self.publish()?;
+        }
+        result
+    }
+
+    /// Update and draw the frame starting at `now`, unless paused.
+    fn render(&mut self, now: Duration) -> Result<(), Error<<B as SmartLedsWrite>::Error>> {
+        if self.paused.is_none() {
+            self.advance(self.animation_time(now), false)?;
+        }
+        // Retry a failed write, even if the animation didn't draw a new frame since
+        if self.retry.is_some_and(|at| at <= now) {
+            self.draw_framebuffer()?;
+        }
+        Ok(())
+    }
+
+    /// Update the animations which are due (or all if `force`d) to the animation time `now`
+    /// and draw the frame.
+    fn advance(
+        &mut self,
+        now: Duration,
+        force: bool,
+    ) -> Result<(), Error<<B as SmartLedsWrite>::Error>> {
+        let due = |frame_rate: Option<Duration>, tick: Duration| {
+            force
+                || match frame_rate {
+                    Some(step) => tick.saturating_add(step) <= now,
+                    None => true,
+                }
+        };
+        let supervised = self.supervisor.is_some();
+        let (clock, frame) = (&self.clock, self.frame);
+        let updated = due(self.frame_rate, self.tick);
+        if updated {
+            self.tick = now;
+            let matrix = match &mut self.fading {
+                Some(fading) => &mut fading.to_matrix,
+                None => &mut self.backend,
+            };
+            let animation = &mut self.animation;
+            let start = clock_time(clock, frame);
+            let result = guard(supervised, || animation.update(now, matrix));
+            self.window
+                .update(clock_time(clock, frame).saturating_sub(start));
+            if let Err(message) = result {
+                self.recover(message);
+                return self.draw_framebuffer();
+            }
+        }
+
+        let mut panicked = None;
+        match &mut self.fading {
+            Some(fading) => {
+                if due(fading.from_frame_rate, fading.from_tick) {
+                    fading.from_tick = now;
+                    let (from, from_matrix) = (&mut fading.from, &mut fading.from_matrix);
+                    let start = clock_time(clock, frame);
+                    let result = guard(supervised, || from.update(now, from_matrix));
+                    self.window
+                        .update(clock_time(clock, frame).saturating_sub(start));
+                    panicked = result
+                        .err()
+                        .map(|message| (from.name().to_string(), message));
+                }
+                if panicked.is_none() {
+                    self.blend(now);
+                }
+            }
+            None if !updated => return Ok(()),
+            None => {}
+        }
+        // The previous animation panicked, skip the rest of the transition
+        if let Some((animation, message)) = panicked {
+            if let Some(fading) = self.fading.take() {
+                self.backend = fading.to_matrix;
+            }
+            if let Some(supervisor) = &mut self.supervisor {
+                supervisor.notify(Incident::Panic { animation, message });
+            }
+        }
+        self.draw_framebuffer()
+    }
+}
+
+/// The current time of `clock`, or `frame` without a clock.
+fn clock_time(clock: &Option<Box<dyn Clock>>, frame: Duration) -> Duration {
+    clock.as_ref().map_or(frame, |clock| clock.now())
+}
+
+/// Call `f`, catching any panic if `supervised`.
+#[cfg(feature = "std")]
+fn guard<T>(supervised: bool, f: impl FnOnce() -> T) -> Result<T, String> {
+    if !supervised {
+        return Ok(f());
+    }
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(super::supervisor::message)
+}
+
+/// Call `f`. Without `std`, panics can't be caught.
+#[cfg(not(feature = "std"))]
+fn guard<T>(_supervised: bool, f: impl FnOnce() -> T) -> Result<T, String> {
+    Ok(f())
+}
+
+/// Frame time for the given `frames per second` rate, rounded to microseconds.
+/// A rate of zero (or below) never starts a new frame on its own.
+fn cycle_time(fps: f32) -> Duration {
+    if fps.is_nan() || fps <= 0.0 {
+        return Duration::MAX;
+    }
+    Duration::from_micros(libm::round(1_000_000.0 / fps as f64) as u64)
+}
+
+/// Changes to a running matrix. Commands are applied by the thread running the matrix
+/// (or via [Matrix::apply]) between two frames, so the animation continues without interruption.
+pub enum Command<S: LedMatrix> {
+    /// Switch to another animation.
+    Animation(Box<dyn Animation<S> + Send>),
+    /// Dimm the LEDs to the given level, see [Dimmable]. `255` is full brightness.
+    Brightness(u8),
+    /// Set the `frames per seconds` (FPS) rate of the matrix, see [MatrixBuilder::fps].
+    Fps(f32),
+    /// Freeze (`true`) or continue (`false`) the animation. The current frame stays lit.
+    /// The animation continues where it was paused, as if no time had passed.
+    Pause(bool),
+    /// Pause the animation and advance it by a single update, one frame time later.
+    Step,
+}
+
+/// Messages sent from a [Handle] to the thread in which its matrix is running.
+#[cfg(feature = "std")]
+enum Control<S: LedMatrix> {
+    /// Apply a command at the next frame.
+    Command(Command<S>),
+    /// Return the matrix from the thread.
+    Stop,
 }
 
 /// Wrapper type for the `JoinHandle` of the thread in which the matrix is running.
 /// This allows for thread-safe sharing of the handle.
-pub struct Handle<S, B>(JoinHandle<Result<Matrix<S, B>, Error<<B as SmartLedsWrite>::Error>>>)
+///
+/// Each handle controls only its own matrix, so several matrices can run independently.
+#[cfg(feature = "std")]
+pub struct Handle<S, B>
 where
     S: LedMatrix<Driver = B>,
     B: SmartLedsWrite + Send,
     B::Error: Send + Debug,
-    <B as SmartLedsWrite>::Color: Clone + Dimmable;
+    <B as SmartLedsWrite>::Color: Clone + Dimmable + Blend + Correctable + PowerDraw,
+{
+    thread: JoinHandle<Result<Matrix<S, B>, Error<<B as SmartLedsWrite>::Error>>>,
+    control: Sender<Control<S>>,
+    telemetry: Arc<Mutex<Telemetry>>,
+}
+
+/// Measurements of the running matrix, shared with its [Handle].
+#[derive(Default)]
+struct Telemetry {
+    /// Estimated current of the last frame in mA, if a [PowerModel] is set.
+    power_draw: Option<u32>,
+    /// Frame timing of the last period, once the first one is over.
+    stats: Option<Stats>,
+    status: Status,
+}
+
+/// Snapshot of the state of a running matrix, see [status].
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Status {
+    /// [Animation::name] of the current animation.
+    pub animation: String,
+    /// [Animation::params] of the current animation.
+    pub params: Vec<(&'static str, String)>,
+    pub brightness: u8,
+    pub fps: f32,
+    pub paused: bool,
+    /// Whether the thread of the matrix is still running.
+    pub running: bool,
+    /// The error which stopped the matrix, e.g. of the driver. While a [Supervisor]
+    /// retries, the last error of the driver, cleared by the next successful write.
+    pub error: Option<String>,
+}
 
-impl<S, B> Handle<S, B>
+/// The types of a matrix running on its own thread, implemented by its [Handle].
+///
+/// The functions controlling a running matrix, e.g. [update] or [status], are generic over
+/// it. This way, the bounds of the matrix, its driver and colors are only written here.
+#[cfg(feature = "std")]
+pub trait Running: Sized {
+    type Matrix: LedMatrix<Driver = Self::Driver> + Send + 'static;
+    type Driver: SmartLedsWrite<Error = Self::Error, Color = Self::Color> + Send + 'static;
+    type Error: Send + Debug;
+    type Color: Clone + Dimmable + Blend + Correctable + PowerDraw;
+
+    /// Stop the matrix and return the underlying instance if it was running.
+    fn stop(self) -> Result<Matrix<Self::Matrix, Self::Driver>, Error<Self::Error>>;
+
+    /// Pass a command to the running matrix.
+    fn send(self, command: Command<Self::Matrix>) -> Result<Self, Error<Self::Error>>;
+
+    /// The handle itself, e.g. to read the telemetry of the matrix.
+    fn handle(&self) -> &Handle<Self::Matrix, Self::Driver>;
+}
+
+#[cfg(feature = "std")]
+impl<S, B> Running for Handle<S, B>
 where
     S: LedMatrix<Driver = B> + Send + 'static,
     B: SmartLedsWrite + Send + 'static,
     B::Error: Send + Debug,
-    <B as SmartLedsWrite>::Color: Clone + Dimmable,
+    <B as SmartLedsWrite>::Color: Clone + Dimmable + Blend + Correctable + PowerDraw,
 {
-    fn start(matrix: Matrix<S, B>) -> Result<Self, Error<<B as SmartLedsWrite>::Error>> {
-        *STOP.lock().map_err(|_| Error::Poisoned)? = false;
-        Ok(Self(matrix.run()))
-    }
+    type Matrix = S;
+    type Driver = B;
+    type Error = B::Error;
+    type Color = B::Color;
 
-    /// Stop the matrix and return the underlying instance if it was running.
-    fn stop(self) -> Result<Matrix<S, B>, Error<<B as SmartLedsWrite>::Error>> {
-        *STOP.lock().map_err(|_| Error::Poisoned)? = true;
-        self.0.join().map_err(|_| Error::Paniced)?
+    fn stop(self) -> Result<Matrix<S, B>, Error<B::Error>> {
+        // Sending fails if the thread already returned, joining yields the reason
+        let _ = self.control.send(Control::Stop);
+        self.thread.join().map_err(|_| Error::Paniced)?
     }
 
-    /// Restart with a new animation (if there is already a running instance).
-    fn update(
-        self,
-        animation: Box<dyn Animation<S> + Send>,
-    ) -> Result<Self, Error<<B as SmartLedsWrite>::Error>> {
-        let mut matrix = self.stop().map_err(|_| Error::Poisoned)?;
-        matrix.set_animation(animation)?;
-        Self::start(matrix)
+    fn send(self, command: Command<S>) -> Result<Self, Error<B::Error>> {
+        match self.control.send(Control::Command(command)) {
+            Ok(()) => Ok(self),
+            // The thread exited on its own, e.g. because of a driver error
+            Err(_) => self.stop().and(Err(Error::Paniced)),
+        }
     }
-    /// Restart with a new animation (if there is already a running instance).
-    fn brightness(self, level: Option<u8>) -> Result<Self, Error<<B as SmartLedsWrite>::Error>> {
-        let mut matrix = self.stop().map_err(|_| Error::Poisoned)?;
-        matrix.brightness = level;
-        matrix.draw_framebuffer()?;
-        Self::start(matrix)
+
+    fn handle(&self) -> &Self {
+        self
     }
 }
 
-/// Change the animation of the matrix.
-pub fn update<S, B>(
-    handle: &Arc<Mutex<Option<Handle<S, B>>>>,
-    animation: Box<dyn Animation<S> + Send>,
-) -> Result<(), Error<<B as SmartLedsWrite>::Error>>
-where
-    S: LedMatrix<Driver = B> + Send + 'static,
-    B: SmartLedsWrite + Send + 'static,
-    B::Error: Send + Debug,
-    <B as SmartLedsWrite>::Color: Clone + Dimmable,
-{
+/// Pass a [Command] to the matrix.
+///
+/// Returns the error which stopped the matrix if it isn't running anymore.
+#[cfg(feature = "std")]
+pub fn send<H: Running>(
+    handle: &Arc<Mutex<Option<H>>>,
+    command: Command<H::Matrix>,
+) -> Result<(), Error<H::Error>> {
     let mut handle = handle.lock().map_err(|_| Error::Poisoned)?;
     if let Some(inner) = handle.take() {
-        let _ = handle.insert(inner.update(animation)?);
+        let _ = handle.insert(inner.send(command)?);
     }
     Ok(())
 }
 
-/// Adjust the brightness of the LEDs by `level`.
+/// Change the animation of the matrix.
+#[cfg(feature = "std")]
+pub fn update<H: Running>(
+    handle: &Arc<Mutex<Option<H>>>,
+    animation: Box<dyn Animation<H::Matrix> + Send>,
+) -> Result<(), Error<H::Error>> {
+    send(handle, Command::Animation(animation))
+}
+
+/// Adjust the brightness of the LEDs to `level / 255`, where `255` is full brightness.
 ///
-/// Levels > 0.5 result in full brightness.
-pub fn brightness<S, B>(
-    handle: &Arc<Mutex<Option<Handle<S, B>>>>,
-    level: Option<f32>,
-) -> Result<(), Error<<B as SmartLedsWrite>::Error>>
-where
-    S: LedMatrix<Driver = B> + Send + 'static,
-    B: SmartLedsWrite + Send + 'static,
-    B::Error: Send + Debug,
-    <B as SmartLedsWrite>::Color: Clone + Dimmable,
-{
+/// Pixels which are on stay (barely) lit at low levels, only `0` turns all LEDs off.
+#[cfg(feature = "std")]
+pub fn brightness<H: Running>(
+    handle: &Arc<Mutex<Option<H>>>,
+    level: u8,
+) -> Result<(), Error<H::Error>> {
+    send(handle, Command::Brightness(level))
+}
+
+/// Change the `frames per second` (FPS) rate of the matrix, see [MatrixBuilder::fps].
+///
+/// The next frame is scheduled one frame time of the new rate after the last one.
+#[cfg(feature = "std")]
+pub fn fps<H: Running>(handle: &Arc<Mutex<Option<H>>>, n: f32) -> Result<(), Error<H::Error>> {
+    send(handle, Command::Fps(n))
+}
+
+/// Freeze the animation. The current frame stays lit until the matrix is resumed.
+#[cfg(feature = "std")]
+pub fn pause<H: Running>(handle: &Arc<Mutex<Option<H>>>) -> Result<(), Error<H::Error>> {
+    send(handle, Command::Pause(true))
+}
+
+/// Continue a paused animation where it was paused, as if no time had passed.
+#[cfg(feature = "std")]
+pub fn resume<H: Running>(handle: &Arc<Mutex<Option<H>>>) -> Result<(), Error<H::Error>> {
+    send(handle, Command::Pause(false))
+}
+
+/// Pause the animation (if it is running) and advance it by a single update.
+///
+/// From the perspective of the animation, one frame time passes per step.
+#[cfg(feature = "std")]
+pub fn step<H: Running>(handle: &Arc<Mutex<Option<H>>>) -> Result<(), Error<H::Error>> {
+    send(handle, Command::Step)
+}
+
+/// The estimated current in mA drawn by the last frame.
+///
+/// Returns `None` if no [PowerModel] was set or the matrix isn't running.
+#[cfg(feature = "std")]
+pub fn power_draw<H: Running>(
+    handle: &Arc<Mutex<Option<H>>>,
+) -> Result<Option<u32>, Error<H::Error>> {
+    let handle = handle.lock().map_err(|_| Error::Poisoned)?;
+    let Some(inner) = handle.as_ref() else {
+        return Ok(None);
+    };
+    let telemetry = inner
+        .handle()
+        .telemetry
+        .lock()
+        .map_err(|_| Error::Poisoned)?;
+    Ok(telemetry.power_draw)
+}
+
+/// Frame timing statistics of the matrix, updated once per [PERIOD](super::stats::PERIOD).
+///
+/// Returns `None` if the matrix isn't running or the first period isn't over yet.
+#[cfg(feature = "std")]
+pub fn stats<H: Running>(handle: &Arc<Mutex<Option<H>>>) -> Result<Option<Stats>, Error<H::Error>> {
+    let handle = handle.lock().map_err(|_| Error::Poisoned)?;
+    let Some(inner) = handle.as_ref() else {
+        return Ok(None);
+    };
+    let telemetry = inner
+        .handle()
+        .telemetry
+        .lock()
+        .map_err(|_| Error::Poisoned)?;
+    Ok(telemetry.stats)
+}
+
+/// The current animation and settings of the matrix, and whether it is still running.
+///
+/// The parameters of the animation are refreshed on every command and once per
+/// [PERIOD](super::stats::PERIOD). Returns `None` if the matrix was stopped.
+#[cfg(feature = "std")]
+pub fn status<H: Running>(
+    handle: &Arc<Mutex<Option<H>>>,
+) -> Result<Option<Status>, Error<H::Error>> {
+    let handle = handle.lock().map_err(|_| Error::Poisoned)?;
+    let Some(inner) = handle.as_ref().map(Running::handle) else {
+        return Ok(None);
+    };
+    let mut status = inner
+        .telemetry
+        .lock()
+        .map_err(|_| Error::Poisoned)?
+        .status
+        .clone();
+    // The thread may have stopped because of an error or a panic
+    status.running &= !inner.thread.is_finished();
+    Ok(Some(status))
+}
+
+/// Stop the matrix. The LEDs keep displaying the last frame.
+///
+/// Returns the error which stopped the matrix if it wasn't running anymore.
+#[cfg(feature = "std")]
+pub fn stop<H: Running>(handle: &Arc<Mutex<Option<H>>>) -> Result<(), Error<H::Error>> {
     let mut handle = handle.lock().map_err(|_| Error::Poisoned)?;
     if let Some(inner) = handle.take() {
-        let _ = handle.insert(inner.brightness(level.map(|v| (1.0 / v.max(0.01).min(1.0)) as u8))?);
+        inner.stop()?;
     }
     Ok(())
 }
+
+// The test drivers count frames in a `Mutex` and many tests use the threaded runner
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use crate::led::{
+        clock::MockClock,
+        layout::{ColumnFirst, Layout},
+        Color,
+    };
+
+    /// Driver counting the frames written to it.
+    /// Fails as many writes as set in the second field.
+    #[derive(Clone, Default)]
+    struct Frames(Arc<Mutex<usize>>, Arc<Mutex<u32>>);
+
+    impl SmartLedsWrite for Frames {
+        type Error = ();
+        type Color = RGB8;
+
+        fn write<T, I>(&mut self, _iterator: T) -> Result<(), Self::Error>
+        where
+            T: Iterator<Item = I>,
+            I: Into<Self::Color>,
+        {
+            let mut failures = self.1.lock().unwrap();
+            if *failures > 0 {
+                *failures -= 1;
+                return Err(());
+            }
+            *self.0.lock().unwrap() += 1;
+            Ok(())
+        }
+    }
+
+    #[derive(Clone, Default)]
+    struct Test([Color<Self>; 4]);
+
+    impl LedMatrix for Test {
+        const X: usize = 2;
+        const Y: usize = 2;
+        type Driver = Frames;
+        type Frame = [Color<Self>; 4];
+
+        fn frame(&self) -> Self::Frame {
+            self.0
+        }
+
+        fn read_buf(&self) -> &[Color<Self>] {
+            &self.0
+        }
+
+        fn set_buf(&mut self, buf: &mut [Color<Self>]) {
+            self.0.copy_from_slice(buf);
+        }
+
+        fn set_2d(&mut self, x: usize, y: usize, color: &Color<Self>) {
+            self.0[Self::index_2d(x, y)] = *color;
+        }
+
+        fn index_2d(x: usize, y: usize) -> usize {
+            y * <Self as LedMatrix>::X + x
+        }
+    }
+
+    /// Animation recording the ticks it was updated with.
+    struct Ticks(Option<Duration>, Arc<Mutex<Vec<Duration>>>);
+
+    impl Animation<Test> for Ticks {
+        fn init(&mut self, _matrix: &mut Test) -> Option<Duration> {
+            self.0
+        }
+
+        fn update(&mut self, tick: Duration, _matrix: &mut Test) {
+            self.1.lock().unwrap().push(tick);
+        }
+    }
+
+    /// Animation filling the matrix with a single color.
+    struct Fill(RGB8);
+
+    impl Animation<Test> for Fill {
+        fn update(&mut self, _tick: Duration, matrix: &mut Test) {
+            matrix.set_buf(&mut [self.0; 4]);
+        }
+    }
+
+    impl Animation<Columns> for Fill {
+        fn update(&mut self, _tick: Duration, matrix: &mut Columns) {
+            matrix.set_buf(&mut [self.0; 4]);
+        }
+    }
+
+    fn matrix(
+        frame_rate: Option<Duration>,
+    ) -> (
+        Matrix<Test, Frames>,
+        MockClock,
+        Arc<Mutex<Vec<Duration>>>,
+        Frames,
+    ) {
+        let clock = MockClock::default();
+        let ticks = Arc::new(Mutex::new(Vec::new()));
+        let frames = Frames::default();
+        let matrix = Matrix::new(Test::default())
+            .animation(Box::new(Ticks(frame_rate, ticks.clone())))
+            .clock(clock.clone())
+            .build(frames.clone())
+            .unwrap();
+        (matrix, clock, ticks, frames)
+    }
+
+    fn ms(n: u64) -> Duration {
+        Duration::from_millis(n)
+    }
+
+    #[test]
+    fn updates_every_frame() {
+        let (mut matrix, clock, ticks, frames) = matrix(None);
+        assert_eq!(*frames.0.lock().unwrap(), 1); // Drawn once after init
+
+        for _ in 0..3 {
+            clock.advance(ms(40));
+            matrix.tick(clock.now()).unwrap();
+        }
+        assert_eq!(*ticks.lock().unwrap(), vec![ms(40), ms(80), ms(120)]);
+        assert_eq!(*frames.0.lock().unwrap(), 4);
+    }
+
+    #[test]
+    fn frame_rate_throttling() {
+        let (mut matrix, clock, ticks, frames) = matrix(Some(ms(100)));
+
+        for _ in 0..5 {
+            clock.advance(ms(50));
+            matrix.tick(clock.now()).unwrap();
+        }
+        assert_eq!(*ticks.lock().unwrap(), vec![ms(100), ms(200)]);
+        assert_eq!(*frames.0.lock().unwrap(), 3);
+        assert_eq!(matrix.frame, ms(250));
+    }
+
+    #[test]
+    fn stats() {
+        let (mut matrix, clock, _, _) = matrix(None);
+        matrix.apply(Command::Fps(25.0)).unwrap();
+
+        for _ in 0..24 {
+            clock.advance(ms(40));
+            matrix.tick(clock.now()).unwrap();
+        }
+        assert_eq!(matrix.stats().unwrap(), None);
+
+        // The last frame is late by two frame times
+        clock.advance(ms(120));
+        matrix.tick(clock.now()).unwrap();
+        let stats = matrix.stats().unwrap().unwrap();
+        assert!((stats.fps - 25.0 / 1.08).abs() < 0.01);
+        assert_eq!(stats.skipped, 2);
+        // The mock clock doesn't move while updating or writing
+        assert_eq!(stats.update_max, Duration::ZERO);
+    }
+
+    #[test]
+    fn fps() {
+        assert_eq!(cycle_time(24.0), Duration::from_micros(41667));
+        assert_eq!(cycle_time(1000.0), ms(1));
+        assert_eq!(cycle_time(0.25), ms(4000));
+        assert_eq!(cycle_time(0.0), Duration::MAX);
+        assert_eq!(cycle_time(f32::NAN), Duration::MAX);
+
+        // Slower than one frame per second
+        let (mut matrix, clock, ticks, _) = matrix(None);
+        matrix.apply(Command::Fps(0.5)).unwrap();
+        for _ in 0..2 {
+            clock.advance(ms(2000));
+            matrix.tick(clock.now()).unwrap();
+        }
+        assert_eq!(*ticks.lock().unwrap(), vec![ms(2000), ms(4000)]);
+        assert_eq!(matrix.status().unwrap().fps, 0.5);
+    }
+
+    #[test]
+    fn without_clock() {
+        // As on targets without std, the time only comes from tick
+        let ticks = Arc::new(Mutex::new(Vec::new()));
+        let mut builder = Matrix::new(Test::default())
+            .animation(Box::new(Ticks(None, ticks.clone())))
+            .fps(10.0);
+        builder.clock = None;
+        let mut matrix = builder.build(Frames::default()).unwrap();
+        assert_eq!(matrix.next_frame(), ms(100));
+
+        matrix.tick(ms(100)).unwrap();
+        assert_eq!(matrix.next_frame(), ms(200));
+        matrix.apply(Command::Pause(true)).unwrap();
+        matrix.tick(ms(200)).unwrap();
+        matrix.apply(Command::Pause(false)).unwrap();
+        matrix.tick(ms(300)).unwrap();
+        assert_eq!(*ticks.lock().unwrap(), vec![ms(100), ms(200)]);
+        assert!(!matrix.status().unwrap().paused);
+    }
+
+    #[test]
+    fn fps_at_runtime() {
+        let (mut matrix, clock, _, frames) = matrix(None);
+        matrix.apply(Command::Fps(0.1)).unwrap();
+        assert_eq!(matrix.next_frame(), ms(10000));
+
+        // The new rate applies right away instead of after the long frame
+        clock.advance(ms(20));
+        matrix.apply(Command::Fps(500.0)).unwrap();
+        assert!(matrix.next_frame() <= clock.now());
+        for _ in 0..10 {
+            matrix.tick(clock.now()).unwrap();
+            assert_eq!(matrix.next_frame(), clock.now() + ms(2));
+            clock.advance(ms(2));
+        }
+        assert_eq!(*frames.0.lock().unwrap(), 11);
+
+        // The running thread gets the new rate as well
+        let handle = Matrix::new(Test::default())
+            .animation(Box::new(Ticks(None, Default::default())))
+            .fps(0.1)
+            .run(Frames::default())
+            .unwrap();
+        super::fps(&handle, 500.0).unwrap();
+        let handle = handle.lock().unwrap().take().unwrap();
+        assert_eq!(handle.stop().unwrap().fps, 500.0);
+    }
+
+    /// Animation panicking on every update.
+    struct Panics;
+
+    impl Animation<Test> for Panics {
+        fn update(&mut self, _tick: Duration, _matrix: &mut Test) {
+            panic!("boom");
+        }
+    }
+
+    fn supervised(
+        animation: Box<dyn Animation<Test> + Send>,
+        supervisor: Supervisor<Test>,
+    ) -> (
+        Matrix<Test, Frames>,
+        MockClock,
+        Frames,
+        Arc<Mutex<Vec<Incident>>>,
+    ) {
+        let clock = MockClock::default();
+        let frames = Frames::default();
+        let incidents = Arc::new(Mutex::new(Vec::new()));
+        let report = incidents.clone();
+        let matrix = Matrix::new(Test::default())
+            .animation(animation)
+            .clock(clock.clone())
+            .supervise(supervisor.report(move |incident| {
+                report.lock().unwrap().push(incident.clone());
+            }))
+            .build(frames.clone())
+            .unwrap();
+        (matrix, clock, frames, incidents)
+    }
+
+    #[test]
+    fn driver_retry() {
+        let supervisor = Supervisor::new().retry(2, ms(100));
+        let (mut matrix, clock, frames, incidents) =
+            supervised(Box::new(Fill(RGB8::new(1, 1, 1))), supervisor);
+
+        *frames.1.lock().unwrap() = 2;
+        for _ in 0..10 {
+            clock.advance(ms(40));
+            matrix.tick(clock.now()).unwrap();
+        }
+        // Written at 40 (failed), 160 (failed, after 100 ms) and 360 ms (after 200 ms)
+        assert_eq!(
+            *incidents.lock().unwrap(),
+            vec![
+                Incident::Driver {
+                    error: "()".into(),
+                    attempt: 1,
+                    backoff: ms(100)
+                },
+                Incident::Driver {
+                    error: "()".into(),
+                    attempt: 2,
+                    backoff: ms(200)
+                },
+            ]
+        );
+        // Drawn after init, then at 360 and 400 ms
+        assert_eq!(*frames.0.lock().unwrap(), 3);
+        assert_eq!(matrix.failures, 0);
+
+        *frames.1.lock().unwrap() = 3;
+        for _ in 0..10 {
+            clock.advance(ms(100));
+            if let Err(err) = matrix.tick(clock.now()) {
+                assert!(matches!(err, Error::Driver(())));
+                break;
+            }
+        }
+        let incidents = incidents.lock().unwrap();
+        assert_eq!(
+            incidents.last(),
+            Some(&Incident::GaveUp { error: "()".into() })
+        );
+    }
+
+    #[test]
+    fn panic_fallback() {
+        let white = RGB8::new(255, 255, 255);
+        let supervisor = Supervisor::new().fallback(move || Box::new(Fill(white)));
+        let (mut matrix, clock, _, incidents) = supervised(Box::new(Panics), supervisor);
+
+        clock.advance(ms(40));
+        matrix.tick(clock.now()).unwrap();
+        let incident = incidents.lock().unwrap().pop();
+        assert!(matches!(
+            incident,
+            Some(Incident::Panic { animation, message }) if animation.ends_with("Panics") && message == "boom"
+        ));
+
+        // The fallback keeps running
+        clock.advance(ms(40));
+        matrix.tick(clock.now()).unwrap();
+        assert_eq!(matrix.backend.0, [white; 4]);
+        assert!(incidents.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn pause_resume_step() {
+        let (mut matrix, clock, ticks, frames) = matrix(None);
+        clock.advance(ms(40));
+        matrix.tick(clock.now()).unwrap();
+
+        matrix.apply(Command::Pause(true)).unwrap();
+        for _ in 0..10 {
+            clock.advance(ms(40));
+            matrix.tick(clock.now()).unwrap();
+        }
+        assert_eq!(*frames.0.lock().unwrap(), 2); // Nothing drawn while paused
+
+        matrix.apply(Command::Step).unwrap();
+        matrix.apply(Command::Step).unwrap();
+        clock.advance(ms(40));
+        matrix.tick(clock.now()).unwrap();
+        let step = Duration::from_micros(41667); // 24 FPS
+        assert_eq!(
+            *ticks.lock().unwrap(),
+            vec![ms(40), ms(40) + step, ms(40) + step * 2]
+        );
+        assert_eq!(*frames.0.lock().unwrap(), 4);
+
+        // Continues one frame after the last step
+        matrix.apply(Command::Pause(false)).unwrap();
+        clock.advance(ms(41));
+        matrix.tick(clock.now()).unwrap();
+        assert_eq!(ticks.lock().unwrap().last(), Some(&(ms(81) + step * 2)));
+    }
+
+    #[test]
+    fn commands() {
+        let (mut matrix, clock, ticks, frames) = matrix(None);
+
+        matrix.apply(Command::Pause(true)).unwrap();
+        clock.advance(ms(40));
+        matrix.tick(clock.now()).unwrap();
+        assert!(ticks.lock().unwrap().is_empty());
+
+        matrix.apply(Command::Brightness(127)).unwrap();
+        assert_eq!(*frames.0.lock().unwrap(), 2); // Redrawn while paused
+
+        matrix.apply(Command::Pause(false)).unwrap();
+        matrix.apply(Command::Fps(10.0)).unwrap();
+        assert_eq!(matrix.cycle_time, ms(100));
+        matrix.tick(clock.now()).unwrap();
+        assert_eq!(*ticks.lock().unwrap(), vec![ms(0)]); // The paused time is skipped
+
+        let replaced = Arc::new(Mutex::new(Vec::new()));
+        matrix
+            .apply(Command::Animation(Box::new(Ticks(None, replaced.clone()))))
+            .unwrap();
+        clock.advance(ms(40));
+        matrix.tick(clock.now()).unwrap();
+        assert_eq!(*ticks.lock().unwrap(), vec![ms(0)]);
+        assert_eq!(*replaced.lock().unwrap(), vec![ms(40)]);
+    }
+
+    #[test]
+    fn crossfade() {
+        let clock = MockClock::default();
+        let mut matrix = Matrix::new(Test::default())
+            .animation(Box::new(Fill(RGB8::new(0, 0, 0))))
+            .clock(clock.clone())
+            .transition(Transition::Crossfade(ms(100)))
+            .build(Frames::default())
+            .unwrap();
+
+        let white = RGB8::new(255, 255, 255);
+        matrix
+            .apply(Command::Animation(Box::new(Fill(white))))
+            .unwrap();
+        assert!(matrix.fading.is_some());
+
+        clock.advance(ms(50));
+        matrix.tick(clock.now()).unwrap();
+        assert_eq!(matrix.backend.0, [RGB8::new(127, 127, 127); 4]);
+
+        clock.advance(ms(50));
+        matrix.tick(clock.now()).unwrap();
+        assert!(matrix.fading.is_none());
+        assert_eq!(matrix.backend.0, [white; 4]);
+    }
+
+    #[test]
+    fn update_keeps_thread() {
+        let (matrix, _, _, _) = matrix(None);
+        let handle = matrix.run();
+        let thread = handle.thread.thread().id();
+
+        let handle = handle
+            .send(Command::Animation(Box::new(Fill(RGB8::new(1, 1, 1)))))
+            .unwrap();
+        assert_eq!(handle.thread.thread().id(), thread);
+
+        // Commands are applied in order, before the thread stops
+        let matrix = handle.stop().unwrap();
+        assert!(matrix.animation.name().ends_with("Fill"));
+    }
+
+    #[test]
+    fn status() {
+        let (mut matrix, _, _, _) = matrix(None);
+        matrix.apply(Command::Brightness(100)).unwrap();
+        matrix.apply(Command::Pause(true)).unwrap();
+        let handle = Arc::new(Mutex::new(Some(matrix.run())));
+
+        let status = super::status(&handle).unwrap().unwrap();
+        assert!(status.animation.ends_with("Ticks"));
+        assert_eq!(status.brightness, 100);
+        assert_eq!(status.fps, 24.0);
+        assert!(status.paused && status.running);
+        assert_eq!(status.error, None);
+
+        stop(&handle).unwrap();
+        assert_eq!(super::status(&handle).unwrap(), None);
+
+        // The last error of a supervised driver is kept until it writes again
+        let supervisor = Supervisor::new().retry(2, ms(100));
+        let (mut matrix, clock, frames, _) =
+            supervised(Box::new(Fill(RGB8::new(1, 1, 1))), supervisor);
+        *frames.1.lock().unwrap() = 1;
+        clock.advance(ms(40));
+        matrix.tick(clock.now()).unwrap();
+        assert_eq!(matrix.status().unwrap().error, Some("()".into()));
+        matrix.apply(Command::Brightness(50)).unwrap();
+        assert_eq!(matrix.status().unwrap().error, Some("()".into()));
+
+        clock.advance(ms(100));
+        matrix.tick(clock.now()).unwrap();
+        assert_eq!(matrix.status().unwrap().error, None);
+    }
+
+    #[test]
+    fn independent_handles() {
+        let run = || {
+            Matrix::new(Test::default())
+                .animation(Box::new(Ticks(None, Default::default())))
+                .fps(100.0)
+                .build(Frames::default())
+                .unwrap()
+                .run()
+        };
+        let first = run();
+        let second = run();
+
+        // Stopping one matrix leaves the other one running and listening to its handle
+        first.stop().unwrap();
+        let second = second.send(Command::Brightness(7)).unwrap();
+        assert_eq!(second.stop().unwrap().brightness, 7);
+    }
+
+    #[test]
+    fn terminal_backend_rows() {
+        let mut backend = TerminalBackend::with_writer(Vec::new(), 2);
+        let frame = [RGB8::new(1, 2, 3); 3];
+
+        backend.write(frame.iter().cloned()).unwrap();
+        let out = String::from_utf8(backend.out.clone()).unwrap();
+        assert!(out.starts_with("\x1b[38;2;1;2;3m"));
+        assert_eq!(out.matches('\n').count(), 2);
+
+        backend.out.clear();
+        backend.write(frame.iter().cloned()).unwrap();
+        let out = String::from_utf8(backend.out.clone()).unwrap();
+        assert!(out.starts_with("\x1b[2A"));
+    }
+
+    /// A 2x2 matrix wired column by column.
+    #[derive(Clone, Default)]
+    struct Columns([RGB8; 4]);
+
+    impl LedMatrix for Columns {
+        const X: usize = 2;
+        const Y: usize = 2;
+        type Driver = Recorder;
+        type Frame = [RGB8; 4];
+
+        fn frame(&self) -> Self::Frame {
+            self.0
+        }
+
+        fn read_buf(&self) -> &[RGB8] {
+            &self.0
+        }
+
+        fn set_buf(&mut self, buf: &mut [RGB8]) {
+            self.0.copy_from_slice(buf);
+        }
+
+        fn set_2d(&mut self, x: usize, y: usize, color: &RGB8) {
+            self.0[Self::index_2d(x, y)] = *color;
+        }
+
+        fn index_2d(x: usize, y: usize) -> usize {
+            ColumnFirst::index(x, y, 2, 2)
+        }
+    }
+
+    /// Lights the pixel at `x = 1` / `y = 0`, one step brighter every second.
+    struct Step(u8);
+
+    impl Animation<Columns> for Step {
+        fn init(&mut self, _matrix: &mut Columns) -> Option<Duration> {
+            Some(Duration::from_secs(1))
+        }
+
+        fn update(&mut self, _tick: Duration, matrix: &mut Columns) {
+            self.0 += 1;
+            matrix.set_2d(1, 0, &RGB8::new(self.0, 0, 0));
+        }
+    }
+
+    #[test]
+    fn record() {
+        let recording = Matrix::new(Columns::default())
+            .animation(Box::new(Step(0)))
+            .fps(4.0)
+            .record(10)
+            .unwrap();
+        assert_eq!((recording.width, recording.height), (2, 2));
+        assert_eq!(recording.length, ms(2500));
+
+        let times: Vec<_> = recording.frames.iter().map(|f| f.time).collect();
+        assert_eq!(times, [ms(0), ms(1000), ms(2000)]);
+        let off = RGB8::default();
+        assert_eq!(
+            recording.frames[1].pixels,
+            [off, RGB8::new(1, 0, 0), off, off]
+        );
+        let delays: Vec<_> = recording.delays().collect();
+        assert_eq!(delays, [ms(1000), ms(1000), ms(500)]);
+    }
+
+    #[test]
+    fn wipe_wiring() {
+        let clock = MockClock::default();
+        let mut matrix = Matrix::new(Columns::default())
+            .animation(Box::new(Fill(RGB8::new(0, 0, 0))))
+            .clock(clock.clone())
+            .transition(Transition::Wipe(ms(100)))
+            .build(Recorder::new(clock.clone()))
+            .unwrap();
+
+        let white = RGB8::new(255, 255, 255);
+        matrix
+            .apply(Command::Animation(Box::new(Fill(white))))
+            .unwrap();
+        clock.advance(ms(50));
+        matrix.tick(clock.now()).unwrap();
+        // The left column is wiped in, the right one half way
+        let half = RGB8::new(125, 125, 125);
+        assert_eq!(matrix.backend.0, [white, white, half, half]);
+    }
+}
@@ -1,11 +1,29 @@
-use std::time::Duration;
+use alloc::{string::String, vec::Vec};
+use core::time::Duration;
 
 use smart_leds_trait::{SmartLedsWrite, RGB8};
 
 use self::hsv_rgb_convert::Hsv8;
 
+pub mod clock;
+pub mod correction;
+pub mod framebuffer;
+#[cfg(feature = "embedded-graphics-core")]
+pub mod graphics;
 pub mod hsv_rgb_convert;
+pub mod layout;
 pub mod matrix;
+pub mod power;
+pub mod record;
+pub mod stats;
+pub mod supervisor;
+pub mod transition;
+
+/// Derive [LedMatrix](trait@LedMatrix) for wrappers, see [framebuffer].
+pub use lux_camp_badge_derive::LedMatrix;
+/// Driver for WS2812 LEDs via the RMT peripheral of the ESP32.
+#[cfg(feature = "esp")]
+pub use ws2812_esp32_rmt_driver::Ws2812Esp32Rmt;
 
 pub type Color<T> = <<T as LedMatrix>::Driver as SmartLedsWrite>::Color;
 
@@ -31,10 +49,25 @@ pub trait LedMatrix {
     const AREA: usize = Self::X * Self::Y;
     /// The total volume of the cube. Usually this is X * Y * Z.
     const VOLUME: usize = Self::X * Self::Y * Self::Z;
+    /// The number of LEDs, i.e. the length of the frame buffer.
+    /// This is the `VOLUME` of LED cubes and the `AREA` of 2D LED matrices.
+    const LEDS: usize = if Self::Z == 0 {
+        Self::AREA
+    } else {
+        Self::VOLUME
+    };
 
     /// The driver for the LED matrix.
     type Driver: SmartLedsWrite;
 
+    /// A whole frame, i.e. one color per LED in the order of [LedMatrix::read_buf],
+    /// e.g. `[Color<Self>; 25]` for a 5x5 matrix.
+    /// Its size is known at compile time, so animations prepare frames without allocating.
+    type Frame: AsRef<[Color<Self>]> + AsMut<[Color<Self>]> + Clone;
+
+    /// A copy of the current frame, e.g. to modify it and write it back via [LedMatrix::set_buf].
+    fn frame(&self) -> Self::Frame;
+
     /// Read the entire internal frame buffer.
     fn read_buf(&self) -> &[Color<Self>]
     where
@@ -43,35 +76,124 @@ pub trait LedMatrix {
     /// Write to the entire internal frame buffer.
     fn set_buf(&mut self, buf: &mut [Color<Self>]);
 
-    /// Write a pixel to the given `x` / `y` coordinate of your 2D LED Matrix.
+    /// Write a pixel to the given `x` / `y` coordinate of your 2D LED Matrix,
+    /// i.e. to the index returned by [LedMatrix::index_2d].
     fn set_2d(&mut self, x: usize, y: usize, color: &Color<Self>);
 
     /// Write a pixel to the given `x` / `y` `z` coordinate of your LED Cube.
     /// 2D LED matrices don't need to implement this function, it'll default to set_2D.
+    /// LED cubes must implement it, e.g. based on a [Layout3d](layout::Layout3d).
     #[allow(unused)]
     fn set_3d(&mut self, x: usize, y: usize, z: usize, color: &Color<Self>) {
         Self::set_2d(self, x, y, color)
     }
+
+    /// Index of the pixel at the given `x` / `y` coordinate in the frame buffer,
+    /// according to how your matrix is wired up, e.g. via a [Layout](layout::Layout).
+    ///
+    /// This is the single source of truth for the wiring: [LedMatrix::set_2d] writes and
+    /// [LedMatrix::get_2d] reads the pixel at this index.
+    fn index_2d(x: usize, y: usize) -> usize;
+
+    /// Read the pixel at the given `x` / `y` coordinate of the current frame,
+    /// e.g. to fade it. Returns `None` outside of the matrix.
+    fn get_2d(&self, x: usize, y: usize) -> Option<&Color<Self>>
+    where
+        Color<Self>: Dimmable,
+    {
+        if x >= Self::X || y >= Self::Y {
+            return None;
+        }
+        self.read_buf().get(Self::index_2d(x, y))
+    }
+
+    /// Index of the pixel at the given `x` / `y` / `z` coordinate in the frame buffer of your
+    /// LED Cube. Defaults to layer by layer, each layer laid out according to [LedMatrix::index_2d].
+    fn index_3d(x: usize, y: usize, z: usize) -> usize {
+        z * Self::AREA + Self::index_2d(x, y)
+    }
+
+    /// Read the pixel at the given `x` / `y` / `z` coordinate of your LED Cube.
+    /// On 2D LED matrices, this is the same as get_2D.
+    fn get_3d(&self, x: usize, y: usize, z: usize) -> Option<&Color<Self>>
+    where
+        Color<Self>: Dimmable,
+    {
+        if Self::Z == 0 {
+            return Self::get_2d(self, x, y);
+        }
+        if x >= Self::X || y >= Self::Y || z >= Self::Z {
+            return None;
+        }
+        self.read_buf().get(Self::index_3d(x, y, z))
+    }
+}
+
+/// Scale `value` by `scale / 256`, like FastLED's `scale8`.
+/// A `scale` of `255` keeps the value as is, `0` turns it off.
+pub fn scale8(value: u8, scale: u8) -> u8 {
+    ((value as u16 * (1 + scale as u16)) >> 8) as u8
+}
+
+/// Like [scale8], but values which are on stay on (unless `scale` is `0`),
+/// like FastLED's `scale8_video`. This avoids dark pixels turning off entirely.
+pub fn scale8_video(value: u8, scale: u8) -> u8 {
+    ((value as u16 * scale as u16) >> 8) as u8 + (value != 0 && scale != 0) as u8
 }
 
 /// RGB or HSV values are dimmable. Usually, full send will bleach your eyes out anyways.
 pub trait Dimmable {
-    /// Dimm `self` according to `level`, where level is a divisor of the full brightness.
+    /// Dimm `self` to `scale / 255` of its brightness, where `255` is full brightness.
     #[allow(unused)]
-    fn dimm(&mut self, level: u8) {}
+    fn dimm(&mut self, scale: u8) {}
 }
 
 impl Dimmable for RGB8 {
-    fn dimm(&mut self, level: u8) {
-        self.r /= level;
-        self.g /= level;
-        self.b /= level;
+    fn dimm(&mut self, scale: u8) {
+        self.r = scale8_video(self.r, scale);
+        self.g = scale8_video(self.g, scale);
+        self.b = scale8_video(self.b, scale);
     }
 }
 
 impl Dimmable for Hsv8 {
-    fn dimm(&mut self, level: u8) {
-        self.val /= level;
+    fn dimm(&mut self, scale: u8) {
+        self.val = scale8_video(self.val, scale);
+    }
+}
+
+/// RGB or HSV values can be mixed, e.g. to transition from one animation to the next.
+pub trait Blend {
+    /// Mix `self` with `other`, where `amount` is the share of `other`:
+    /// `0` results in `self` and `255` results in `other`.
+    fn blend(&self, other: &Self, amount: u8) -> Self;
+}
+
+/// Linear interpolation from `a` to `b` by `amount / 255`.
+fn lerp(a: u8, b: u8, amount: u8) -> u8 {
+    let amount = amount as u32;
+    ((a as u32 * (255 - amount) + b as u32 * amount + 127) / 255) as u8
+}
+
+impl Blend for RGB8 {
+    fn blend(&self, other: &Self, amount: u8) -> Self {
+        RGB8 {
+            r: lerp(self.r, other.r, amount),
+            g: lerp(self.g, other.g, amount),
+            b: lerp(self.b, other.b, amount),
+        }
+    }
+}
+
+impl Blend for Hsv8 {
+    fn blend(&self, other: &Self, amount: u8) -> Self {
+        // Take the shorter way around the color wheel
+        let distance = other.hue.wrapping_sub(self.hue) as i8 as i32;
+        Hsv8 {
+            hue: self.hue.wrapping_add((distance * amount as i32 / 255) as u8),
+            sat: lerp(self.sat, other.sat, amount),
+            val: lerp(self.val, other.val, amount),
+        }
     }
 }
 
@@ -97,4 +219,156 @@ pub trait Animation<C: LedMatrix> {
 
     /// The draw function of your Animation, called at every frame.
     fn update(&mut self, tick: Duration, matrix: &mut C) {}
+
+    /// A short name of your Animation, e.g. to show it in a user interface.
+    /// Defaults to the name of the type.
+    fn name(&self) -> &str {
+        core::any::type_name::<Self>()
+    }
+
+    /// The parameters of your Animation as `(name, value)` pairs,
+    /// e.g. to show them in a user interface.
+    fn params(&self) -> Vec<(&'static str, String)> {
+        Vec::new()
+    }
+}
+
+/// Driver discarding all pixels, for the tests of the matrices and animations.
+#[cfg(test)]
+pub(crate) struct TestDriver;
+
+#[cfg(test)]
+impl SmartLedsWrite for TestDriver {
+    type Error = ();
+    type Color = RGB8;
+
+    fn write<T, I>(&mut self, _iterator: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = I>,
+        I: Into<Self::Color>,
+    {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use layout::{Layout, Serpentine};
+
+    #[test]
+    fn scale8_range() {
+        for value in 0..=255 {
+            assert_eq!(scale8(value, 255), value);
+            assert_eq!(scale8(value, 0), 0);
+            assert_eq!(scale8_video(value, 255), value);
+            assert_eq!(scale8_video(value, 0), 0);
+            assert!(value == 0 || scale8_video(value, 1) > 0);
+        }
+    }
+
+    #[test]
+    fn brightness_steps() {
+        // Every level results in a distinct brightness for fully lit pixels
+        for scale in 0..=255 {
+            let mut pixel = RGB8::new(255, 255, 255);
+            pixel.dimm(scale);
+            assert_eq!(pixel, RGB8::new(scale, scale, scale));
+        }
+    }
+
+    /// A 3x2 matrix, wired row by row.
+    struct Rows([RGB8; 6]);
+
+    impl LedMatrix for Rows {
+        const X: usize = 3;
+        const Y: usize = 2;
+        type Driver = TestDriver;
+        type Frame = [RGB8; 6];
+
+        fn frame(&self) -> Self::Frame {
+            self.0
+        }
+
+        fn read_buf(&self) -> &[RGB8] {
+            &self.0
+        }
+
+        fn set_buf(&mut self, buf: &mut [RGB8]) {
+            self.0.copy_from_slice(buf);
+        }
+
+        fn set_2d(&mut self, x: usize, y: usize, color: &RGB8) {
+            self.0[Self::index_2d(x, y)] = *color;
+        }
+
+        fn index_2d(x: usize, y: usize) -> usize {
+            y * 3 + x
+        }
+    }
+
+    #[test]
+    fn read_back() {
+        let mut matrix = Rows([RGB8::default(); 6]);
+        for (x, y) in [(0, 0), (2, 0), (1, 1), (2, 1)] {
+            matrix.set_2d(x, y, &RGB8::new(x as u8, y as u8, 1));
+            assert_eq!(matrix.get_2d(x, y), Some(&RGB8::new(x as u8, y as u8, 1)));
+        }
+        assert_eq!(matrix.get_2d(3, 0), None);
+        assert_eq!(matrix.get_2d(0, 2), None);
+        assert_eq!(matrix.get_3d(2, 1, 5), matrix.get_2d(2, 1));
+
+        // Fade a pixel in place
+        let mut pixel = *matrix.get_2d(2, 1).unwrap();
+        pixel.dimm(0);
+        matrix.set_2d(2, 1, &pixel);
+        assert_eq!(matrix.get_2d(2, 1), Some(&RGB8::default()));
+    }
+
+    /// A 3x2 matrix, wired row by row in a zigzag.
+    struct Zigzag([RGB8; 6]);
+
+    impl LedMatrix for Zigzag {
+        const X: usize = 3;
+        const Y: usize = 2;
+        type Driver = TestDriver;
+        type Frame = [RGB8; 6];
+
+        fn frame(&self) -> Self::Frame {
+            self.0
+        }
+
+        fn read_buf(&self) -> &[RGB8] {
+            &self.0
+        }
+
+        fn set_buf(&mut self, buf: &mut [RGB8]) {
+            self.0.copy_from_slice(buf);
+        }
+
+        fn set_2d(&mut self, x: usize, y: usize, color: &RGB8) {
+            self.0[Self::index_2d(x, y)] = *color;
+        }
+
+        fn index_2d(x: usize, y: usize) -> usize {
+            Serpentine::index(x, y, 3, 2)
+        }
+    }
+
+    #[test]
+    fn read_back_wiring() {
+        let mut matrix = Zigzag([RGB8::default(); 6]);
+        for y in 0..2 {
+            for x in 0..3 {
+                matrix.set_2d(x, y, &RGB8::new(x as u8, y as u8, 1));
+            }
+        }
+        for y in 0..2 {
+            for x in 0..3 {
+                assert_eq!(matrix.get_2d(x, y), Some(&RGB8::new(x as u8, y as u8, 1)));
+            }
+        }
+        // The second row is stored from right to left
+        assert_eq!(matrix.read_buf()[3], RGB8::new(2, 1, 1));
+    }
 }
@@ -0,0 +1,145 @@
+//! Power budget for the LED matrix, similar to FastLED's `power_mgt`.
+//!
+//! The current drawn by each frame is estimated according to a [PowerModel].
+//! If it exceeds the budget, the whole frame is dimmed until it fits.
+use smart_leds_trait::RGB8;
+
+use super::{
+    hsv_rgb_convert::{Hsv2Rgb, Hsv8},
+    scale8,
+};
+
+/// Electrical properties of the LEDs and the maximum current they may draw.
+///
+/// All currents are in mA. The defaults are typical values of WS2812 LEDs.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PowerModel {
+    /// Current of a fully lit red channel.
+    pub red: u32,
+    /// Current of a fully lit green channel.
+    pub green: u32,
+    /// Current of a fully lit blue channel.
+    pub blue: u32,
+    /// Current of a single LED when it is off.
+    pub idle: u32,
+    /// Maximum current of all LEDs together.
+    pub budget: u32,
+}
+
+impl PowerModel {
+    /// Typical WS2812 LEDs which may draw at most `budget` mA.
+    pub fn ws2812(budget: u32) -> Self {
+        Self {
+            red: 16,
+            green: 11,
+            blue: 15,
+            idle: 1,
+            budget,
+        }
+    }
+
+    /// The scale (see [PowerDraw::reduce]) to keep `leds` LEDs drawing
+    /// a `current` (in µA) within the budget, or `None` if no dimming is required.
+    pub fn limit(&self, leds: usize, current: u32) -> Option<u8> {
+        let budget = self.budget.saturating_mul(1000);
+        if current <= budget {
+            return None;
+        }
+        // The idle current can't be dimmed
+        let idle = self.idle.saturating_mul(1000).saturating_mul(leds as u32);
+        let dimmable = (current - idle.min(current)).max(1) as u64;
+        let available = budget.saturating_sub(idle) as u64;
+        // The estimate of each LED is rounded down by less than 1 µA
+        let scale = available * 256 / (dimmable + leds as u64);
+        Some(scale.saturating_sub(1).min(254) as u8)
+    }
+}
+
+/// RGB or HSV values draw current when they are sent to the LEDs.
+pub trait PowerDraw {
+    /// Estimated current in µA of a single LED displaying `self`.
+    #[allow(unused)]
+    fn current(&self, model: &PowerModel) -> u32 {
+        0
+    }
+
+    /// Scale `self` by `scale / 256` as returned by [PowerModel::limit].
+    /// Unlike [Dimmable](super::Dimmable), dim channels may turn off to stay within the budget.
+    #[allow(unused)]
+    fn reduce(&mut self, scale: u8) {}
+}
+
+impl PowerDraw for RGB8 {
+    fn current(&self, model: &PowerModel) -> u32 {
+        let channels =
+            self.r as u32 * model.red + self.g as u32 * model.green + self.b as u32 * model.blue;
+        (channels * 1000 / 255) + model.idle * 1000
+    }
+
+    fn reduce(&mut self, scale: u8) {
+        self.r = scale8(self.r, scale);
+        self.g = scale8(self.g, scale);
+        self.b = scale8(self.b, scale);
+    }
+}
+
+impl PowerDraw for Hsv8 {
+    fn current(&self, model: &PowerModel) -> u32 {
+        <Hsv8 as Hsv2Rgb>::hsv2rgb(*self).current(model)
+    }
+
+    fn reduce(&mut self, scale: u8) {
+        self.val = scale8(self.val, scale);
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn estimate() {
+        let model = PowerModel::ws2812(500);
+        assert_eq!(RGB8::new(0, 0, 0).current(&model), 1000);
+        assert_eq!(RGB8::new(255, 255, 255).current(&model), 43000);
+        assert_eq!(RGB8::new(255, 0, 0).current(&model), 17000);
+    }
+
+    #[test]
+    fn limit() {
+        let model = PowerModel::ws2812(500);
+        assert_eq!(model.limit(25, 25 * 17000), None);
+
+        // 25 white LEDs draw 1075 mA, of which 1050 mA can be dimmed to fit 500 mA
+        let scale = model.limit(25, 25 * 43000).unwrap();
+        assert_eq!(scale, (475 * 256 / 1050) as u8 - 1);
+
+        // Not even the idle current fits
+        assert_eq!(PowerModel::ws2812(10).limit(25, 25 * 43000), Some(0));
+    }
+
+    #[test]
+    fn dim_frames() {
+        for budget in [26, 30, 50, 100, 500] {
+            let model = PowerModel::ws2812(budget);
+            for val in [1, 2, 5, 20, 100, 255] {
+                let rgb = [RGB8::new(val, val / 2, val); 25];
+                let hsv = Hsv8 {
+                    hue: 42,
+                    sat: 255,
+                    val,
+                };
+                assert!(drawn(&model, rgb) <= budget * 1000);
+                assert!(drawn(&model, [hsv; 25]) <= budget * 1000);
+            }
+        }
+    }
+
+    fn drawn<C: PowerDraw>(model: &PowerModel, mut frame: [C; 25]) -> u32 {
+        let current = frame.iter().map(|pixel| pixel.current(model)).sum();
+        if let Some(scale) = model.limit(frame.len(), current) {
+            frame.iter_mut().for_each(|pixel| pixel.reduce(scale));
+        }
+        frame.iter().map(|pixel| pixel.current(model)).sum()
+    }
+}
@@ -0,0 +1,191 @@
+//! Record the output of an animation on the host, e.g. to preview it in the docs.
+//!
+//! Frames are captured by [MatrixBuilder::record](super::matrix::MatrixBuilder::record),
+//! which runs the matrix with a [Recorder] as driver on a simulated clock.
+//! With the `gif` feature enabled, a [Recording] can be exported as an animated GIF.
+use alloc::{boxed::Box, vec::Vec};
+use core::{convert::Infallible, time::Duration};
+
+use smart_leds_trait::{SmartLedsWrite, RGB8};
+
+use super::clock::Clock;
+
+/// A single frame written to the LEDs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Frame {
+    /// When the frame was written, according to the clock of the [Recorder].
+    pub time: Duration,
+    pub pixels: Vec<RGB8>,
+}
+
+/// Driver that keeps every frame written to it instead of lighting any LEDs.
+pub struct Recorder {
+    clock: Box<dyn Clock>,
+    frames: Vec<Frame>,
+}
+
+impl Recorder {
+    /// Record frames, timestamped by `clock`.
+    pub fn new(clock: impl Clock + 'static) -> Self {
+        Self {
+            clock: Box::new(clock),
+            frames: Vec::new(),
+        }
+    }
+
+    /// The frames recorded so far, in LED order.
+    pub fn frames(&self) -> &[Frame] {
+        &self.frames
+    }
+
+    pub fn into_frames(self) -> Vec<Frame> {
+        self.frames
+    }
+}
+
+impl SmartLedsWrite for Recorder {
+    type Error = Infallible;
+    type Color = RGB8;
+
+    fn write<T, I>(&mut self, iterator: T) -> Result<(), Self::Error>
+    where
+        T: Iterator<Item = I>,
+        I: Into<Self::Color>,
+    {
+        self.frames.push(Frame {
+            time: self.clock.now(),
+            pixels: iterator.map(Into::into).collect(),
+        });
+        Ok(())
+    }
+}
+
+/// Frames of an animation with their pixels in row-major order, i.e. `y * width + x`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Recording {
+    pub width: usize,
+    pub height: usize,
+    /// Each frame stays lit until the next one, the first one starts at zero.
+    pub frames: Vec<Frame>,
+    /// Total length of the recording.
+    pub length: Duration,
+}
+
+impl Recording {
+    /// How long each of the [frames](Recording::frames) is shown.
+    pub fn delays(&self) -> impl Iterator<Item = Duration> + '_ {
+        let ends = self.frames.iter().skip(1).map(|f| f.time);
+        self.frames
+            .iter()
+            .zip(ends.chain([self.length]))
+            .map(|(frame, end)| end.saturating_sub(frame.time))
+    }
+
+    /// Write the recording as looping animated GIF to `out`.
+    /// Each LED is drawn as a square of `scale` x `scale` pixels.
+    ///
+    /// Fails if the scaled image exceeds the 65535 x 65535 pixels of a GIF.
+    /// Delays exceeding the 655.35 s of a GIF frame are cut short.
+    #[cfg(feature = "gif")]
+    pub fn write_gif<W: std::io::Write>(
+        &self,
+        out: W,
+        scale: u16,
+    ) -> Result<(), gif::EncodingError> {
+        let scale = scale.max(1) as usize;
+        let size = |leds: usize| {
+            let pixels = leds.checked_mul(scale)?;
+            Some((pixels, u16::try_from(pixels).ok()?))
+        };
+        let (Some((width, gif_width)), Some((height, gif_height))) =
+            (size(self.width), size(self.height))
+        else {
+            let error = std::io::Error::new(std::io::ErrorKind::InvalidInput, "GIF too large");
+            return Err(error.into());
+        };
+        let mut encoder = gif::Encoder::new(out, gif_width, gif_height, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        // GIF delays are in 1/100 s. Round the start of each frame instead of
+        // the delays, so the rounding errors don't add up.
+        let centis = |time: Duration| (time.as_millis() + 5) / 10;
+        let mut shown = 0;
+        for (frame, delay) in self.frames.iter().zip(self.delays()) {
+            let end = centis(frame.time + delay);
+            if end <= shown {
+                continue;
+            }
+            let mut rgb = Vec::with_capacity(width * height * 3);
+            for y in 0..height {
+                for x in 0..width {
+                    let pixel = frame.pixels[y / scale * self.width + x / scale];
+                    rgb.extend([pixel.r, pixel.g, pixel.b]);
+                }
+            }
+            let mut image = gif::Frame::from_rgb_speed(gif_width, gif_height, &rgb, 10);
+            image.delay = u16::try_from(end - shown).unwrap_or(u16::MAX);
+            encoder.write_frame(&image)?;
+            shown = end;
+        }
+        Ok(())
+    }
+}
+
+// The tests run on the `MockClock`, which requires `std`
+#[cfg(all(test, feature = "std"))]
+mod test {
+    use super::*;
+    use crate::led::clock::MockClock;
+
+    #[test]
+    fn recorder() {
+        let clock = MockClock::default();
+        let mut recorder = Recorder::new(clock.clone());
+        recorder.write([RGB8::new(1, 2, 3)].into_iter()).unwrap();
+        clock.advance(Duration::from_millis(40));
+        recorder.write([RGB8::new(4, 5, 6)].into_iter()).unwrap();
+
+        let recording = Recording {
+            width: 1,
+            height: 1,
+            frames: recorder.into_frames(),
+            length: Duration::from_millis(100),
+        };
+        assert_eq!(recording.frames[1].time, Duration::from_millis(40));
+        assert_eq!(recording.frames[1].pixels, [RGB8::new(4, 5, 6)]);
+        let delays: Vec<_> = recording.delays().map(|d| d.as_millis()).collect();
+        assert_eq!(delays, [40, 60]);
+    }
+
+    #[test]
+    #[cfg(feature = "gif")]
+    fn gif() {
+        let frame = |ms, pixels: [RGB8; 2]| Frame {
+            time: Duration::from_millis(ms),
+            pixels: pixels.into(),
+        };
+        let red = RGB8::new(255, 0, 0);
+        let recording = Recording {
+            width: 2,
+            height: 1,
+            frames: vec![
+                frame(0, [red, RGB8::default()]),
+                frame(40, [RGB8::default(), red]),
+            ],
+            length: Duration::from_millis(100),
+        };
+        let mut out = Vec::new();
+        recording.write_gif(&mut out, 3).unwrap();
+
+        let mut decoder = gif::Decoder::new(out.as_slice()).unwrap();
+        assert_eq!((decoder.width(), decoder.height()), (6, 3));
+        let mut delays = Vec::new();
+        while let Some(frame) = decoder.read_next_frame().unwrap() {
+            assert_eq!((frame.width, frame.height), (6, 3));
+            delays.push(frame.delay);
+        }
+        assert_eq!(delays, [4, 6]);
+
+        assert!(recording.write_gif(Vec::new(), u16::MAX).is_err());
+    }
+}
@@ -0,0 +1,127 @@
+//! Frame timing statistics of a running matrix.
+//!
+//! The matrix measures every frame and publishes a [Stats] summary once per [PERIOD],
+//! which can be read via [matrix::stats](super::matrix::stats).
+use core::time::Duration;
+
+/// How often the statistics are updated.
+pub const PERIOD: Duration = Duration::from_secs(1);
+
+/// Frame timing of the last [PERIOD]. All durations are measured by the clock of the matrix.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Stats {
+    /// Frames per second the matrix actually achieved.
+    pub fps: f32,
+    /// Average time spent in [Animation::update](super::Animation::update) per update.
+    pub update_avg: Duration,
+    /// Longest time spent in a single update.
+    pub update_max: Duration,
+    /// Average time spent writing a frame to the driver.
+    pub write_avg: Duration,
+    /// Longest time spent writing a single frame.
+    pub write_max: Duration,
+    /// Frames missed since the matrix was started, because a frame took
+    /// longer than the frame time of the `fps` rate.
+    pub skipped: u64,
+}
+
+/// Measurements of the current period.
+#[derive(Default)]
+pub(crate) struct Window {
+    start: Duration,
+    frames: u32,
+    updates: u32,
+    update_total: Duration,
+    update_max: Duration,
+    writes: u32,
+    write_total: Duration,
+    write_max: Duration,
+    skipped: u64,
+}
+
+impl Window {
+    pub(crate) fn new(start: Duration) -> Self {
+        Self {
+            start,
+            ..Default::default()
+        }
+    }
+
+    /// Count a frame starting `elapsed` after the previous one.
+    pub(crate) fn frame(&mut self, elapsed: Duration, cycle_time: Duration) {
+        self.frames += 1;
+        let cycles = elapsed.as_micros() / cycle_time.as_micros().max(1);
+        self.skipped += cycles.saturating_sub(1) as u64;
+    }
+
+    pub(crate) fn update(&mut self, duration: Duration) {
+        self.updates += 1;
+        self.update_total += duration;
+        self.update_max = self.update_max.max(duration);
+    }
+
+    pub(crate) fn write(&mut self, duration: Duration) {
+        self.writes += 1;
+        self.write_total += duration;
+        self.write_max = self.write_max.max(duration);
+    }
+
+    /// Summarize the period once it is over at `now` and start the next one.
+    pub(crate) fn finish(&mut self, now: Duration) -> Option<Stats> {
+        let elapsed = now.saturating_sub(self.start);
+        if elapsed < PERIOD {
+            return None;
+        }
+        let stats = Stats {
+            fps: self.frames as f32 / elapsed.as_secs_f32(),
+            update_avg: self.update_total / self.updates.max(1),
+            update_max: self.update_max,
+            write_avg: self.write_total / self.writes.max(1),
+            write_max: self.write_max,
+            skipped: self.skipped,
+        };
+        *self = Self {
+            start: now,
+            skipped: self.skipped,
+            ..Default::default()
+        };
+        Some(stats)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn window() {
+        let mut window = Window::new(ms(0));
+        for _ in 0..20 {
+            window.frame(ms(50), ms(50));
+            window.update(ms(10));
+            window.write(ms(2));
+        }
+        window.update(ms(30));
+        // A frame which took three frame times
+        window.frame(ms(150), ms(50));
+        assert_eq!(window.finish(ms(900)), None);
+
+        let stats = window.finish(ms(1050)).unwrap();
+        assert!((stats.fps - 20.0).abs() < 0.01);
+        assert_eq!(stats.update_avg, ms(230) / 21);
+        assert_eq!(stats.update_max, ms(30));
+        assert_eq!(stats.write_avg, ms(2));
+        assert_eq!(stats.skipped, 2);
+
+        // The next period starts over, except for the skipped frames
+        window.frame(ms(50), ms(50));
+        let stats = window.finish(ms(2050)).unwrap();
+        assert_eq!(stats.fps, 1.0);
+        assert_eq!(stats.update_max, Duration::ZERO);
+        assert_eq!(stats.skipped, 2);
+    }
+}
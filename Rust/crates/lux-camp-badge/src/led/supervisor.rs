@@ -0,0 +1,212 @@
+//! Keep the matrix running when its driver or animation fails.
+//!
+//! Without a [Supervisor], a driver error stops the matrix and a panicking animation
+//! takes down the thread of the matrix. Once set via
+//! [MatrixBuilder::supervise](super::matrix::MatrixBuilder::supervise):
+//!
+//! * Failed writes are retried on later frames, waiting longer after every failure.
+//! * Panicking animations are replaced by the fallback animation.
+//! * Every [Incident] is passed to the reporting callback.
+//!
+//! Panics are caught via [std::panic::catch_unwind],
+//! so they are only isolated if the target is built to unwind on panics and `std` is enabled.
+//! The badge itself builds `std` with `panic_abort` (see `.cargo/config.toml`): there is
+//! no panic recovery on the ESP, a panicking animation still aborts the firmware.
+use alloc::{boxed::Box, string::String};
+use core::time::Duration;
+
+use super::{Animation, LedMatrix};
+
+/// Something that went wrong while the matrix was running.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Incident {
+    /// The driver failed to write a frame for the `attempt`th time in a row.
+    /// The next write is attempted after `backoff`.
+    Driver {
+        error: String,
+        attempt: u32,
+        backoff: Duration,
+    },
+    /// The driver failed more often than allowed, the matrix stops.
+    GaveUp { error: String },
+    /// An animation panicked and was replaced by the fallback.
+    Panic { animation: String, message: String },
+}
+
+/// Policy for recovering from failures of a running matrix.
+///
+/// ```ignore
+/// let handle = Matrix::new(matrix)
+///     .animation(noise::PerlinAnimation::build())
+///     .supervise(
+///         Supervisor::new()
+///             .retry(10, Duration::from_millis(50))
+///             .fallback(|| rainbow::Fade::build(1, None))
+///             .report(|incident| println!("{incident:?}")),
+///     )
+///     .run(driver)?;
+/// ```
+pub struct Supervisor<S: LedMatrix> {
+    retries: u32,
+    backoff: Duration,
+    max_backoff: Duration,
+    fallback: Option<Fallback<S>>,
+    report: Option<Report>,
+}
+
+type Fallback<S> = Box<dyn Fn() -> Box<dyn Animation<S> + Send> + Send>;
+type Report = Box<dyn FnMut(&Incident) + Send>;
+
+impl<S: LedMatrix> Supervisor<S> {
+    /// Retries failed writes forever, starting with a backoff of 100 ms.
+    /// Panicking animations are replaced by an animation keeping the last frame.
+    pub fn new() -> Self {
+        Self {
+            retries: u32::MAX,
+            backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+            fallback: None,
+            report: None,
+        }
+    }
+
+    /// Give up after `retries` failed writes in a row. The first retry is after `backoff`,
+    /// which doubles with every further failure, up to [max_backoff](Self::max_backoff).
+    pub fn retry(mut self, retries: u32, backoff: Duration) -> Self {
+        self.retries = retries;
+        self.backoff = backoff;
+        self
+    }
+
+    /// Upper limit of the time between two retries, 10 s by default.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Create the animation replacing a panicking one.
+    /// Only used on targets which unwind on panics, i.e. not on the ESP.
+    pub fn fallback(
+        mut self,
+        fallback: impl Fn() -> Box<dyn Animation<S> + Send> + Send + 'static,
+    ) -> Self {
+        self.fallback = Some(Box::new(fallback));
+        self
+    }
+
+    /// Call `report` on every incident, from the thread of the matrix.
+    pub fn report(mut self, report: impl FnMut(&Incident) + Send + 'static) -> Self {
+        self.report = Some(Box::new(report));
+        self
+    }
+
+    /// Whether to stop the matrix after the `attempt`th failure in a row.
+    pub(crate) fn gives_up(&self, attempt: u32) -> bool {
+        attempt > self.retries
+    }
+
+    /// How long to wait after the `attempt`th failure in a row.
+    pub(crate) fn backoff(&self, attempt: u32) -> Duration {
+        let factor = 1u32
+            .checked_shl(attempt.saturating_sub(1))
+            .unwrap_or(u32::MAX);
+        self.backoff.saturating_mul(factor).min(self.max_backoff)
+    }
+
+    /// A new instance of the fallback animation.
+    pub(crate) fn fallback_animation(&self) -> Box<dyn Animation<S> + Send> {
+        match &self.fallback {
+            Some(fallback) => fallback(),
+            None => Box::new(Idle),
+        }
+    }
+
+    pub(crate) fn notify(&mut self, incident: Incident) {
+        if let Some(report) = &mut self.report {
+            report(&incident);
+        }
+    }
+}
+
+impl<S: LedMatrix> Default for Supervisor<S> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Keeps the frame buffer as it is.
+pub(crate) struct Idle;
+
+impl<S: LedMatrix> Animation<S> for Idle {
+    fn init(&mut self, _matrix: &mut S) -> Option<Duration> {
+        Some(Duration::MAX)
+    }
+
+    fn name(&self) -> &str {
+        "idle"
+    }
+}
+
+/// The message of a caught panic.
+#[cfg(feature = "std")]
+pub(crate) fn message(payload: Box<dyn core::any::Any + Send>) -> String {
+    match payload.downcast::<String>() {
+        Ok(message) => *message,
+        Err(payload) => match payload.downcast::<&str>() {
+            Ok(message) => String::from(*message),
+            Err(_) => "unknown panic".into(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::led::TestDriver;
+    use alloc::vec::Vec;
+    use smart_leds_trait::RGB8;
+
+    struct Test;
+
+    impl LedMatrix for Test {
+        type Driver = TestDriver;
+        type Frame = [RGB8; 0];
+        const X: usize = 1;
+        const Y: usize = 1;
+
+        fn frame(&self) -> Self::Frame {
+            []
+        }
+
+        fn read_buf(&self) -> &[RGB8] {
+            &[]
+        }
+
+        fn set_buf(&mut self, _buf: &mut [RGB8]) {}
+
+        fn set_2d(&mut self, _x: usize, _y: usize, _color: &RGB8) {}
+
+        fn index_2d(_x: usize, _y: usize) -> usize {
+            0
+        }
+    }
+
+    #[test]
+    fn backoff() {
+        let supervisor = Supervisor::<Test>::new()
+            .retry(5, Duration::from_millis(100))
+            .max_backoff(Duration::from_secs(1));
+        let backoff: Vec<_> = (1..=6).map(|n| supervisor.backoff(n).as_millis()).collect();
+        assert_eq!(backoff, [100, 200, 400, 800, 1000, 1000]);
+        assert_eq!(supervisor.backoff(u32::MAX), Duration::from_secs(1));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn messages() {
+        let caught = std::panic::catch_unwind(|| panic!("at {}", 42)).unwrap_err();
+        assert_eq!(message(caught), "at 42");
+        let caught = std::panic::catch_unwind(|| panic!("static")).unwrap_err();
+        assert_eq!(message(caught), "static");
+    }
+}
@@ -0,0 +1,96 @@
+//! Transitions between animations.
+//!
+//! When enabled via [MatrixBuilder::transition](super::matrix::MatrixBuilder::transition),
+//! the previous and the new animation keep running side by side, each in its own copy of the
+//! frame buffer. Both frames are then mixed (see [Blend](super::Blend)) into the frame
+//! buffer of the LED matrix until the transition is completed.
+use core::time::Duration;
+
+/// How to switch from one animation to the next.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Transition {
+    /// Fade the new animation in while fading the previous one out.
+    Crossfade(Duration),
+    /// Sweep the new animation in from left to right.
+    Wipe(Duration),
+    /// Switch to the new animation pixel by pixel, in random order.
+    Dissolve(Duration),
+}
+
+impl Transition {
+    /// How long the transition takes.
+    pub fn duration(&self) -> Duration {
+        match self {
+            Self::Crossfade(duration) | Self::Wipe(duration) | Self::Dissolve(duration) => {
+                *duration
+            }
+        }
+    }
+
+    /// Progress of the transition after `elapsed` time, from `0` (start) to `255` (done).
+    pub(crate) fn progress(&self, elapsed: Duration) -> u8 {
+        let duration = self.duration().as_micros().max(1);
+        (elapsed.as_micros().min(duration) * 255 / duration) as u8
+    }
+
+    /// The share of the new animation for the pixel at `x` / `y` at the given `progress`.
+    pub(crate) fn amount(&self, x: usize, y: usize, width: usize, progress: u8) -> u8 {
+        match self {
+            Self::Crossfade(_) => progress,
+            Self::Wipe(_) => {
+                // The edge moves over the entire width with a soft border of one pixel
+                let edge = progress as f32 / 255.0 * (width + 1) as f32;
+                ((edge - x as f32).clamp(0.0, 1.0) * 255.0) as u8
+            }
+            Self::Dissolve(_) => {
+                // Fibonacci hashing spreads the thresholds of neighboring pixels
+                let threshold = ((y * width + x) as u32).wrapping_mul(2654435769) >> 24;
+                if progress as u32 > threshold.min(254) {
+                    255
+                } else {
+                    0
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn progress() {
+        let fade = Transition::Crossfade(Duration::from_millis(100));
+        assert_eq!(fade.progress(Duration::ZERO), 0);
+        assert_eq!(fade.progress(Duration::from_millis(50)), 127);
+        assert_eq!(fade.progress(Duration::from_millis(100)), 255);
+        assert_eq!(fade.progress(Duration::from_secs(1)), 255);
+        assert_eq!(Transition::Wipe(Duration::ZERO).progress(Duration::ZERO), 0);
+    }
+
+    #[test]
+    fn wipe() {
+        let wipe = Transition::Wipe(Duration::from_secs(1));
+        assert!((0..5).all(|x| wipe.amount(x, 0, 5, 0) == 0));
+        assert!((0..5).all(|x| wipe.amount(x, 0, 5, 255) == 255));
+        let half: Vec<_> = (0..5).map(|x| wipe.amount(x, 3, 5, 127)).collect();
+        assert_eq!(half[0], 255);
+        assert_eq!(half[4], 0);
+        assert!(half.windows(2).all(|w| w[0] >= w[1]));
+    }
+
+    #[test]
+    fn dissolve() {
+        let dissolve = Transition::Dissolve(Duration::from_secs(1));
+        let switched = |progress| {
+            (0..25)
+                .filter(|i| dissolve.amount(i % 5, i / 5, 5, progress) == 255)
+                .count()
+        };
+        assert_eq!(switched(0), 0);
+        assert!(switched(127) > 0 && switched(127) < 25);
+        assert_eq!(switched(255), 25);
+    }
+}
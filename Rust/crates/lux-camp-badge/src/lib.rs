@@ -1,8 +1,13 @@
-#![allow(incomplete_features)]
-#![feature(generic_const_exprs)]
+#![cfg_attr(not(feature = "std"), no_std)]
+// Lets the derive macros refer to this crate from within itself
+extern crate self as lux_camp_badge;
+// Without `std`, the LED core only needs an allocator
+extern crate alloc;
+
 /// Traits and implementations for writing animations that can
 /// run on a variety of modern (e.g. neopixel-like) LED matrices.
 pub mod led;
 
 /// Common helpers for connecting to a Wifi network.
+#[cfg(feature = "esp")]
 pub mod wifi;
@@ -7,10 +7,16 @@ use embedded_svc::{http::Method, io::Write};
 use esp_idf_hal::gpio::PinDriver;
 use esp_idf_hal::peripheral::Peripheral;
 use esp_idf_hal::peripherals::Peripherals;
-use esp_idf_svc::systime::EspSystemTime;
 use esp_idf_svc::wifi::EspWifi;
 use esp_idf_sys::{self as _}; // If using the `binstart` feature of `esp-idf-sys`, always keep this module imported
+use lux_camp_badge::led::clock::{Clock, EspClock};
+use lux_camp_badge::led::correction::{Correction, TYPICAL_LED_STRIP};
+use lux_camp_badge::led::framebuffer::FrameBuffer;
+use lux_camp_badge::led::layout::{FlipX, Progressive};
 use lux_camp_badge::led::matrix::{self, Handle, Matrix};
+use lux_camp_badge::led::power::PowerModel;
+use lux_camp_badge::led::supervisor::Supervisor;
+use lux_camp_badge::led::transition::Transition;
 use lux_camp_badge::led::{Animation, Color, LedMatrix};
 use lux_camp_badge_animations::prelude::*;
 use serde::Deserialize;
@@ -31,28 +37,8 @@ const LED_PIN: u32 = 1;
 const LED_CHANNEL: u8 = 0;
 static INDEX_HTML: &str = include_str!("json_post_handler.html");
 
-#[derive(Default)]
-struct LuxBadge([Color<Self>; <Self as LedMatrix>::AREA]);
-
-impl LedMatrix for LuxBadge {
-    const X: usize = 5;
-    const Y: usize = 5;
-    type Driver = Ws2812Esp32Rmt;
-
-    fn read_buf(&self) -> &[Color<Self>] {
-        &self.0
-    }
-
-    fn set_buf(&mut self, buf: &mut [Color<Self>]) {
-        if buf.len() == <Self as LedMatrix>::AREA {
-            self.0.copy_from_slice(buf);
-        }
-    }
-
-    fn set_2d(&mut self, x: usize, y: usize, color: &Color<Self>) {
-        self.0[(y * <Self as LedMatrix>::Y) + (<Self as LedMatrix>::X - 1 - x)] = *color;
-    }
-}
+/// The 5x5 matrix of the badge, its columns are wired from right to left.
+type LuxBadge = FrameBuffer<Ws2812Esp32Rmt, 5, 5, FlipX<Progressive>>;
 
 /// The default static scene conveniently turns our LED matrix off.
 type Off = Box<
@@ -81,6 +67,11 @@ struct FormDataAnimation<'a> {
     animation: &'a str,
 }
 
+#[derive(Deserialize)]
+struct FormDataText {
+    text: String,
+}
+
 #[derive(Copy, Clone)]
 pub enum Animations {
     Rainbow,
@@ -134,6 +125,8 @@ fn start_web_server(
     led_matrix: Arc<Mutex<Option<Handle<LuxBadge, Ws2812Esp32Rmt>>>>,
 ) -> EspHttpServer {
     let mut server = EspHttpServer::new(&Configuration::default()).unwrap();
+    // Shared with the marquee, so a new text shows up right away
+    let marquee_text = text::Text::new("Hallo Lux-Camp!");
 
     // http://<sta ip>/ handler
     server
@@ -157,6 +150,37 @@ fn start_web_server(
         .unwrap();
 
     let h = Arc::clone(&led_matrix);
+    server
+        .fn_handler("/fps", Method::Get, move |req| {
+            let fps = req
+                .uri()
+                .split("?val=")
+                .nth(1)
+                .and_then(|v| v.parse::<f32>().ok())
+                .ok_or_else(|| HandlerError::new("invalid fps value"))?;
+            matrix::fps(&h, fps).map_err(|_| HandlerError::new("matrix error"))?;
+            req.into_ok_response()?;
+            Ok(())
+        })
+        .unwrap();
+
+    let h = Arc::clone(&led_matrix);
+    server
+        .fn_handler("/pause", Method::Get, move |req| {
+            match req.uri().split("?set=").nth(1) {
+                Some("on") => matrix::pause(&h),
+                Some("off") => matrix::resume(&h),
+                Some("step") => matrix::step(&h),
+                _ => return Err(HandlerError::new("invalid pause value")),
+            }
+            .map_err(|_| HandlerError::new("matrix error"))?;
+            req.into_ok_response()?;
+            Ok(())
+        })
+        .unwrap();
+
+    let h = Arc::clone(&led_matrix);
+    let t = marquee_text.clone();
     server
         .fn_handler("/animation", Method::Post, move |mut req| {
             let len = req.content_len().unwrap_or(0) as usize;
@@ -173,7 +197,7 @@ fn start_web_server(
 
             serde_json::from_slice::<FormDataAnimation>(&buf)
                 .map(|form| {
-                    let seed = EspSystemTime {}.now().as_millis() as u64;
+                    let seed = EspClock.now().as_millis() as u64;
                     let animation: Box<dyn Animation<LuxBadge> + Send + 'static> =
                         match form.animation {
                             "rainbow" => rainbow::Fade::build(1, None),
@@ -181,6 +205,9 @@ fn start_web_server(
                             "flip" => random::Flip::build(seed),
                             "random" => random::P30::build(seed),
                             "perlin" => noise::PerlinAnimation::build(),
+                            "text" => text::Marquee::new(t.clone())
+                                .foreground(text::Fill::Rainbow)
+                                .build(),
                             "gol" => gol::Gol::<
                                 Color<LuxBadge>,
                                 { <LuxBadge as LedMatrix>::X },
@@ -225,11 +252,94 @@ fn start_web_server(
         })
         .unwrap();
 
+    let h = Arc::clone(&led_matrix);
+    server
+        .fn_handler("/text", Method::Post, move |mut req| {
+            let len = req.content_len().unwrap_or(0) as usize;
+
+            if len > MAX_LEN {
+                req.into_status_response(413)?
+                    .write_all("Request too big".as_bytes())?;
+                return Ok(());
+            }
+
+            let mut buf = vec![0; len];
+            req.read_exact(&mut buf)?;
+            let mut resp = req.into_ok_response()?;
+
+            serde_json::from_slice::<FormDataText>(&buf)
+                .map(|form| {
+                    marquee_text.set(&form.text);
+                    let status = matrix::status(&h).ok().flatten();
+                    if status.map_or(true, |status| status.animation != text::Marquee::NAME) {
+                        let marquee = text::Marquee::new(marquee_text.clone())
+                            .foreground(text::Fill::Rainbow)
+                            .build();
+                        matrix::update(&h, marquee).unwrap();
+                    }
+                    write!(resp, "Text {}", form.text)
+                })
+                .map_err(|_| resp.write_all("JSON error".as_bytes()))??;
+
+            Ok(())
+        })
+        .unwrap();
+
+    let h = Arc::clone(&led_matrix);
+    server
+        .fn_handler("/stats", Method::Get, move |request| {
+            let stats = matrix::stats(&h)
+                .map_err(|_| HandlerError::new(r#"{ "error": "matrix failure" }"#))?
+                .unwrap_or_default();
+
+            request.into_ok_response()?.write(
+                format!(
+                    "fps: {:.1}, update: {:?} (max {:?}), write: {:?} (max {:?}), skipped: {}",
+                    stats.fps,
+                    stats.update_avg,
+                    stats.update_max,
+                    stats.write_avg,
+                    stats.write_max,
+                    stats.skipped
+                )
+                .as_bytes(),
+            )?;
+            Ok(())
+        })
+        .unwrap();
+
+    let h = Arc::clone(&led_matrix);
+    server
+        .fn_handler("/state", Method::Get, move |request| {
+            let Some(status) = matrix::status(&h)
+                .map_err(|_| HandlerError::new(r#"{ "error": "matrix failure" }"#))?
+            else {
+                return Err(HandlerError::new(r#"{ "error": "matrix stopped" }"#));
+            };
+
+            let state = serde_json::json!({
+                "animation": status.animation,
+                "params": status.params,
+                "brightness": status.brightness,
+                "fps": status.fps,
+                "paused": status.paused,
+                "running": status.running,
+                "error": status.error,
+            });
+            request
+                .into_ok_response()?
+                .write_all(state.to_string().as_bytes())?;
+            Ok(())
+        })
+        .unwrap();
+
     server
         .fn_handler("/brightness", Method::Get, move |request| {
             let level = match request.uri().split("?val=").nth(1) {
-                Some("off") => None,
-                Some(v) => v.parse::<u8>().map(|v| v as f32 / 100.0).ok(),
+                Some("off") => u8::MAX,
+                Some(v) => v
+                    .parse::<u8>()
+                    .map_err(|_| HandlerError::new("invalid brightness value"))?,
                 _ => return Err(HandlerError::new("invalid brightness value")),
             };
 
@@ -238,7 +348,7 @@ fn start_web_server(
 
             request
                 .into_ok_response()?
-                .write(format!("brightness: {}%", level.unwrap_or(0.5) * 200.0).as_bytes())?;
+                .write(format!("brightness: {}%", level as u32 * 100 / 255).as_bytes())?;
             Ok(())
         })
         .unwrap();
@@ -258,7 +368,14 @@ fn main() -> ! {
 
     // Setup HTTP server and LED matrix
     let led_matrix = Matrix::new(LuxBadge::default())
-        .animation(random::P30::build(EspSystemTime {}.now().as_millis() as u64))
+        .animation(random::P30::build(EspClock.now().as_millis() as u64))
+        .clock(EspClock)
+        .transition(Transition::Crossfade(Duration::from_millis(500)))
+        .correction(Correction::new().gamma(2.2).color(TYPICAL_LED_STRIP))
+        .power(PowerModel::ws2812(500))
+        // Retry failed writes. There is no fallback for panicking animations:
+        // the ESP is built with `panic_abort`, so a panic can't be recovered from.
+        .supervise(Supervisor::new().report(|incident| println!("LED matrix: {incident:?}")))
         .run(Ws2812Esp32Rmt::new(LED_CHANNEL, LED_PIN).unwrap())
         .unwrap();
     let _wifi = connect_wifi(modem);
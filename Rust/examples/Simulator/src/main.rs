@@ -0,0 +1,103 @@
+//! Runs any of our animations in the terminal on the host, or records them as GIF.
+//!
+//! Usage: `simulator [animation] [fps] [output.gif] [frames]`
+use lux_camp_badge::led::framebuffer::FrameBuffer;
+use lux_camp_badge::led::matrix::{Matrix, TerminalBackend};
+use lux_camp_badge::led::record::Recorder;
+use lux_camp_badge::led::Animation;
+use lux_camp_badge_animations::prelude::*;
+use smart_leds_trait::{SmartLedsWrite, RGB8};
+use std::fs::File;
+use std::io::BufWriter;
+use std::thread::sleep;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+const WIDTH: usize = 5;
+const HEIGHT: usize = 5;
+
+/// A 5x5 matrix like the badge, wired row by row.
+/// The driver is either the terminal or a recorder.
+type Simulated<D> = FrameBuffer<D, WIDTH, HEIGHT>;
+
+fn animation<D>(name: &str, seed: u64) -> Option<Box<dyn Animation<Simulated<D>> + Send>>
+where
+    D: SmartLedsWrite<Color = RGB8> + 'static,
+{
+    Some(match name {
+        "rainbow" => rainbow::Fade::build(1, None),
+        "rainbow-slide" => rainbow::Slide::build(5, None),
+        "flip" => random::Flip::build(seed),
+        "random" => random::P30::build(seed),
+        "perlin" => noise::PerlinAnimation::build(),
+        "gol" => gol::Gol::<RGB8, WIDTH, HEIGHT>::build(
+            seed,
+            0.5,
+            Some(128),
+            Some(Duration::from_millis(250)),
+        ),
+        "layers" => layers::Layers::new()
+            .layer(
+                noise::PerlinAnimation::build(),
+                layers::BlendMode::Normal,
+                layers::Opacity::new(255),
+            )
+            .layer(
+                random::Flip::build(seed),
+                layers::BlendMode::Screen,
+                layers::Opacity::new(96),
+            )
+            .build(),
+        "playlist" => playlist::Playlist::new()
+            .add(rainbow::Slide::build(5, None), Duration::from_secs(10))
+            .add(noise::PerlinAnimation::build(), Duration::from_secs(10))
+            .add(random::Flip::build(seed), Duration::from_secs(5))
+            .shuffle(seed)
+            .build(),
+        "text" => text::Marquee::new("Hallo Lux-Camp!")
+            .foreground(text::Fill::Rainbow)
+            .build(),
+        _ => return None,
+    })
+}
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let name = args.next().unwrap_or_else(|| "rainbow-slide".into());
+    let fps = args.next().and_then(|n| n.parse().ok()).unwrap_or(24.0);
+    let output = args.next();
+    let frames = args.next().and_then(|n| n.parse().ok()).unwrap_or(120);
+
+    let seed = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|t| t.as_millis() as u64)
+        .unwrap_or_default();
+    if animation::<TerminalBackend>(&name, seed).is_none() {
+        eprintln!("Unknown animation '{name}'");
+        eprintln!(
+            "Available: rainbow, rainbow-slide, flip, random, perlin, gol, layers, playlist, text"
+        );
+        std::process::exit(1);
+    }
+
+    if let Some(path) = output {
+        let recording = Matrix::new(Simulated::<Recorder>::default())
+            .animation(animation(&name, seed).unwrap())
+            .fps(fps)
+            .record(frames)
+            .unwrap();
+        let file = BufWriter::new(File::create(&path).unwrap());
+        recording.write_gif(file, 32).unwrap();
+        println!("Recorded {} frames to {path}", recording.frames.len());
+        return;
+    }
+
+    let _matrix = Matrix::new(Simulated::default())
+        .animation(animation(&name, seed).unwrap())
+        .fps(fps)
+        .run(TerminalBackend::new(WIDTH))
+        .unwrap();
+
+    loop {
+        sleep(Duration::from_secs(1));
+    }
+}